@@ -0,0 +1,69 @@
+//! Minimal command line handling shared by the day binaries.
+
+#[derive(Debug, Clone, Default)]
+pub struct Args {
+    args: Vec<String>,
+}
+
+impl Args {
+    pub fn new<I: IntoIterator<Item = S>, S: Into<String>>(args: I) -> Self {
+        Self {
+            args: args.into_iter().map(Into::into).collect(),
+        }
+    }
+
+    pub fn from_env() -> Self {
+        Self::new(std::env::args().skip(1))
+    }
+
+    /// Removes the subcommand `name` if it is the first argument, and reports
+    /// whether it was.
+    pub fn command(&mut self, name: &str) -> bool {
+        let found = self.args.first().is_some_and(|arg| arg == name);
+        if found {
+            self.args.remove(0);
+        }
+        found
+    }
+
+    /// Removes `--name` and reports whether it was present.
+    pub fn flag(&mut self, name: &str) -> bool {
+        let flag = format!("--{name}");
+        let len = self.args.len();
+        self.args.retain(|arg| *arg != flag);
+        self.args.len() != len
+    }
+
+    /// Removes `--name value` or `--name=value` and returns the value.
+    pub fn value(&mut self, name: &str) -> Result<Option<String>, String> {
+        let flag = format!("--{name}");
+        let prefix = format!("--{name}=");
+
+        let Some(idx) = self
+            .args
+            .iter()
+            .position(|arg| *arg == flag || arg.starts_with(&prefix))
+        else {
+            return Ok(None);
+        };
+
+        let arg = self.args.remove(idx);
+        if let Some(value) = arg.strip_prefix(&prefix) {
+            return Ok(Some(value.to_string()));
+        }
+
+        if idx < self.args.len() {
+            Ok(Some(self.args.remove(idx)))
+        } else {
+            Err(format!("missing value for {flag}"))
+        }
+    }
+
+    /// Fails if any argument was left unconsumed.
+    pub fn finish(self) -> Result<(), String> {
+        match self.args.first() {
+            Some(arg) => Err(format!("unexpected argument `{arg}`")),
+            None => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,210 @@
+//! Aho–Corasick automaton for finding many patterns in a single pass.
+//!
+//! The automaton works on bytes, so UTF-8 patterns only ever match on char
+//! boundaries of a UTF-8 haystack.
+
+/// Sentinel for transitions that have not been filled in yet.
+const MISSING: usize = usize::MAX;
+
+#[derive(Debug, Clone)]
+pub struct Automaton<V> {
+    /// Maps each byte to its equivalence class. Bytes that appear in no
+    /// pattern share class 0, which keeps the transition table small.
+    classes: [u16; 256],
+    stride: usize,
+    transitions: Vec<usize>,
+    /// Patterns ending at each state, including those reached through
+    /// failure links.
+    outputs: Vec<Vec<usize>>,
+    patterns: Vec<(usize, V)>,
+    max_len: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Match<'a, V> {
+    pub start: usize,
+    pub end: usize,
+    pub value: &'a V,
+}
+
+impl<V> Match<'_, V> {
+    pub fn len(&self) -> usize {
+        self.end - self.start
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.start == self.end
+    }
+
+    pub fn range(&self) -> std::ops::Range<usize> {
+        self.start..self.end
+    }
+}
+
+impl<V> Automaton<V> {
+    pub fn new<P: AsRef<[u8]>>(patterns: impl IntoIterator<Item = (P, V)>) -> Self {
+        let patterns = patterns
+            .into_iter()
+            .filter(|(pattern, _)| !pattern.as_ref().is_empty())
+            .collect::<Vec<_>>();
+
+        let mut classes = [0; 256];
+        let mut stride = 1;
+        for (pattern, _) in &patterns {
+            for &byte in pattern.as_ref() {
+                if classes[byte as usize] == 0 {
+                    classes[byte as usize] = stride as u16;
+                    stride += 1;
+                }
+            }
+        }
+
+        let mut automaton = Self {
+            classes,
+            stride,
+            transitions: vec![MISSING; stride],
+            outputs: vec![Vec::new()],
+            patterns: Vec::with_capacity(patterns.len()),
+            max_len: 0,
+        };
+
+        for (pattern, value) in patterns {
+            let pattern = pattern.as_ref();
+
+            let mut state = 0;
+            for &byte in pattern {
+                let idx = automaton.index(state, byte);
+                if automaton.transitions[idx] == MISSING {
+                    automaton.transitions[idx] = automaton.outputs.len();
+                    automaton
+                        .transitions
+                        .extend(std::iter::repeat_n(MISSING, stride));
+                    automaton.outputs.push(Vec::new());
+                }
+                state = automaton.transitions[idx];
+            }
+
+            automaton.outputs[state].push(automaton.patterns.len());
+            automaton.patterns.push((pattern.len(), value));
+            automaton.max_len = automaton.max_len.max(pattern.len());
+        }
+
+        automaton.build();
+        automaton
+    }
+
+    fn index(&self, state: usize, byte: u8) -> usize {
+        state * self.stride + self.classes[byte as usize] as usize
+    }
+
+    /// Turns the trie into a full transition table by following failure
+    /// links in breadth-first order.
+    fn build(&mut self) {
+        let mut fail = vec![0; self.outputs.len()];
+        let mut queue = std::collections::VecDeque::new();
+
+        for class in 0..self.stride {
+            match self.transitions[class] {
+                MISSING => self.transitions[class] = 0,
+                next => queue.push_back(next),
+            }
+        }
+
+        while let Some(state) = queue.pop_front() {
+            let inherited = self.outputs[fail[state]].clone();
+            self.outputs[state].extend(inherited);
+
+            for class in 0..self.stride {
+                let fallback = self.transitions[fail[state] * self.stride + class];
+                let idx = state * self.stride + class;
+                match self.transitions[idx] {
+                    MISSING => self.transitions[idx] = fallback,
+                    next => {
+                        fail[next] = fallback;
+                        queue.push_back(next);
+                    }
+                }
+            }
+        }
+    }
+
+    pub fn max_len(&self) -> usize {
+        self.max_len
+    }
+
+    /// Finds every match, including overlapping ones, ordered by end offset
+    /// with the longest match first.
+    pub fn find_overlapping<'a>(
+        &'a self,
+        haystack: &'a [u8],
+    ) -> impl Iterator<Item = Match<'a, V>> + 'a {
+        haystack
+            .iter()
+            .enumerate()
+            .scan(0, move |state, (idx, &byte)| {
+                *state = self.transitions[self.index(*state, byte)];
+                Some((idx + 1, *state))
+            })
+            .flat_map(move |(end, state)| {
+                self.outputs[state].iter().map(move |&pattern| {
+                    let (len, value) = &self.patterns[pattern];
+                    Match {
+                        start: end - len,
+                        end,
+                        value,
+                    }
+                })
+            })
+    }
+
+    /// Finds the match that starts first, preferring the longest one when
+    /// several start at the same offset. Scanning stops as soon as no later
+    /// match can start earlier.
+    pub fn leftmost<'a>(&'a self, haystack: &'a [u8]) -> Option<Match<'a, V>> {
+        let mut best: Option<Match<'a, V>> = None;
+
+        for found in self.find_overlapping(haystack) {
+            if let Some(best) = &best {
+                if found.end > best.start + self.max_len {
+                    break;
+                }
+            }
+
+            if best.as_ref().is_none_or(|best| {
+                (found.start, std::cmp::Reverse(found.len()))
+                    < (best.start, std::cmp::Reverse(best.len()))
+            }) {
+                best = Some(found);
+            }
+        }
+
+        best
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_overlapping() {
+        let automaton = Automaton::new([("he", 1), ("she", 2), ("his", 3), ("hers", 4)]);
+
+        let found = automaton
+            .find_overlapping(b"ushers")
+            .map(|found| (found.range(), *found.value))
+            .collect::<Vec<_>>();
+
+        assert_eq!(found, [(1..4, 2), (2..4, 1), (2..6, 4)]);
+    }
+
+    #[test]
+    fn test_leftmost() {
+        let automaton = Automaton::new([("one", 1), ("twentyone", 21), ("twenty", 20)]);
+
+        let found = automaton.leftmost(b"xtwentyone").unwrap();
+        assert_eq!((found.range(), *found.value), (1..10, 21));
+
+        assert!(automaton.leftmost(b"xyz").is_none());
+    }
+}
@@ -0,0 +1,255 @@
+//! Solves one day over every input file in a directory.
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::process::ExitCode;
+
+use crate::runner::{self, Options, Outcome, Report};
+use crate::Error;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    Ok,
+    Mismatch,
+    ParseError,
+    Failed,
+    TimedOut,
+}
+
+impl std::fmt::Display for Status {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Status::Ok => "ok",
+            Status::Mismatch => "mismatch",
+            Status::ParseError => "parse error",
+            Status::Failed => "error",
+            Status::TimedOut => "timed out",
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Entry {
+    pub file: String,
+    pub report: Report,
+    pub expected: Option<[String; 2]>,
+}
+
+impl Entry {
+    fn outcomes(&self) -> [&Outcome; 2] {
+        [&self.report.part_1, &self.report.part_2]
+    }
+
+    pub fn is_mismatch(&self) -> bool {
+        let Some(expected) = &self.expected else {
+            return false;
+        };
+
+        self.outcomes().into_iter().zip(expected).any(
+            |(outcome, expected)| !matches!(outcome, Outcome::Solved(answer) if answer == expected),
+        )
+    }
+
+    pub fn status(&self) -> Status {
+        let outcomes = self.outcomes();
+
+        if self.report.parse_error().is_some() {
+            Status::ParseError
+        } else if outcomes
+            .iter()
+            .any(|outcome| matches!(outcome, Outcome::TimedOut(_)))
+        {
+            Status::TimedOut
+        } else if outcomes
+            .iter()
+            .any(|outcome| matches!(outcome, Outcome::Failed(_)))
+        {
+            Status::Failed
+        } else if self.is_mismatch() {
+            Status::Mismatch
+        } else {
+            Status::Ok
+        }
+    }
+}
+
+/// Parses an expected-answers file. Each line holds a file name followed by
+/// the part 1 and part 2 answers; blank lines and `#` comments are ignored.
+pub fn parse_expected(contents: &str) -> Result<HashMap<String, [String; 2]>, String> {
+    contents
+        .lines()
+        .enumerate()
+        .map(|(idx, line)| (idx, line.trim()))
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+        .map(|(idx, line)| {
+            let mut splits = line.split_whitespace();
+            match (splits.next(), splits.next(), splits.next(), splits.next()) {
+                (Some(file), Some(part_1), Some(part_2), None) => {
+                    Ok((file.to_string(), [part_1.to_string(), part_2.to_string()]))
+                }
+                _ => Err(format!("invalid expected answers on line {}", idx + 1)),
+            }
+        })
+        .collect()
+}
+
+/// Solves every file in `dir`, in file name order.
+pub fn solve_dir<S, A, B>(
+    day: u8,
+    dir: &Path,
+    expected: &HashMap<String, [String; 2]>,
+    options: &Options,
+    parse: impl Fn(Vec<String>) -> Result<S, Error> + Clone + Send + 'static,
+    part_1: impl Fn(&S) -> Result<A, Error> + Clone + Send + 'static,
+    part_2: impl Fn(&S) -> Result<B, Error> + Clone + Send + 'static,
+) -> Result<Vec<Entry>, String>
+where
+    A: std::fmt::Display,
+    B: std::fmt::Display,
+{
+    let read_err = |err: std::io::Error| format!("cannot read {}: {err}", dir.display());
+
+    let mut paths = std::fs::read_dir(dir)
+        .map_err(read_err)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(read_err)?;
+    paths.retain(|path| path.is_file());
+    paths.sort();
+
+    paths
+        .into_iter()
+        .map(|path| {
+            let file = path
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            let input = std::fs::read_to_string(&path)
+                .map_err(|err| format!("cannot read {}: {err}", path.display()))?;
+
+            let report = runner::solve(
+                day,
+                input.lines().map(str::to_string).collect(),
+                options,
+                parse.clone(),
+                part_1.clone(),
+                part_2.clone(),
+            );
+
+            Ok(Entry {
+                expected: expected.get(&file).cloned(),
+                file,
+                report,
+            })
+        })
+        .collect()
+}
+
+/// Renders the entries as a table of file, answers, time and status.
+pub fn table(entries: &[Entry]) -> String {
+    let mut rows = vec![[
+        "file".to_string(),
+        "part 1".to_string(),
+        "part 2".to_string(),
+        "time".to_string(),
+        "status".to_string(),
+    ]];
+
+    rows.extend(entries.iter().map(|entry| {
+        let cell = |idx: usize, outcome: &Outcome| match (&entry.expected, outcome) {
+            (Some(expected), Outcome::Solved(answer)) if *answer != expected[idx] => {
+                format!("{answer} (expected {})", expected[idx])
+            }
+            _ => outcome.to_string(),
+        };
+
+        [
+            entry.file.clone(),
+            cell(0, &entry.report.part_1),
+            cell(1, &entry.report.part_2),
+            format!("{:.2?}", entry.report.elapsed),
+            entry.status().to_string(),
+        ]
+    }));
+
+    crate::table::render(&rows)
+}
+
+pub fn exit_code(entries: &[Entry]) -> ExitCode {
+    if entries
+        .iter()
+        .any(|entry| matches!(entry.status(), Status::ParseError | Status::Failed))
+    {
+        ExitCode::FAILURE
+    } else if entries
+        .iter()
+        .any(|entry| entry.status() == Status::TimedOut)
+    {
+        ExitCode::from(runner::TIMEOUT_EXIT_CODE)
+    } else {
+        ExitCode::SUCCESS
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Numbers(Vec<i64>);
+
+    fn parse(lines: Vec<String>) -> Result<Numbers, Error> {
+        lines
+            .iter()
+            .map(|line| {
+                line.parse()
+                    .map_err(|_| Error::Parse(format!("invalid number `{line}`")))
+            })
+            .collect::<Result<_, _>>()
+            .map(Numbers)
+    }
+
+    fn sum(numbers: &Numbers) -> Result<i64, Error> {
+        Ok(numbers.0.iter().sum())
+    }
+
+    fn max(numbers: &Numbers) -> Result<i64, Error> {
+        Ok(numbers.0.iter().copied().max().unwrap_or_default())
+    }
+
+    #[test]
+    fn test_parse_expected() {
+        let expected = parse_expected("# file part1 part2\n\na 1 2\n").unwrap();
+
+        assert_eq!(expected["a"], ["1".to_string(), "2".to_string()]);
+        assert!(parse_expected("a 1").is_err());
+    }
+
+    #[test]
+    fn test_solve_dir() {
+        let dir = std::env::temp_dir().join(format!("aoc-batch-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("a"), "1\n2\n3\n").unwrap();
+        std::fs::write(dir.join("b"), "4\n5\n").unwrap();
+        std::fs::write(dir.join("c"), "6\nseven\n").unwrap();
+
+        let expected = parse_expected("a 6 3\nb 9 4\n").unwrap();
+        let entries = solve_dir(0, &dir, &expected, &Options::default(), parse, sum, max).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let statuses = entries
+            .iter()
+            .map(|entry| (entry.file.as_str(), entry.status()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            statuses,
+            [
+                ("a", Status::Ok),
+                ("b", Status::Mismatch),
+                ("c", Status::ParseError)
+            ]
+        );
+        assert!(table(&entries).contains("5 (expected 4)"));
+        assert!(table(&entries).contains("invalid input: invalid number `seven`"));
+        assert_eq!(exit_code(&entries), ExitCode::FAILURE);
+    }
+}
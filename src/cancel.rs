@@ -0,0 +1,52 @@
+//! Cooperative cancellation for solver runs.
+//!
+//! The runner installs a [`Token`] on the thread that parses and solves the
+//! input. Long loops call [`checkpoint`] so a run that has exceeded its time
+//! budget stops at the next opportunity instead of spinning forever.
+
+use std::cell::RefCell;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cancelled;
+
+impl std::fmt::Display for Cancelled {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("cancelled")
+    }
+}
+
+impl std::error::Error for Cancelled {}
+
+#[derive(Debug, Clone, Default)]
+pub struct Token(Arc<AtomicBool>);
+
+impl Token {
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+thread_local! {
+    static CURRENT: RefCell<Option<Token>> = const { RefCell::new(None) };
+}
+
+/// Installs `token` as the cancellation token of the current thread.
+pub fn install(token: Token) {
+    CURRENT.with(|current| *current.borrow_mut() = Some(token));
+}
+
+/// Returns `Err(Cancelled)` once the current run has been cancelled.
+///
+/// Threads without an installed token are never cancelled.
+pub fn checkpoint() -> Result<(), Cancelled> {
+    CURRENT.with(|current| match current.borrow().as_ref() {
+        Some(token) if token.is_cancelled() => Err(Cancelled),
+        _ => Ok(()),
+    })
+}
@@ -0,0 +1,232 @@
+//! Arithmetic helpers used by the solvers.
+//!
+//! With the `checked` feature enabled every operation is overflow checked and
+//! reports an [`Overflow`] naming the day, the operation and both operands.
+//! Without the feature the helpers compile down to the plain operators.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Op {
+    Add,
+    Sub,
+    Mul,
+    Pow,
+}
+
+impl std::fmt::Display for Op {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Op::Add => "+",
+            Op::Sub => "-",
+            Op::Mul => "*",
+            Op::Pow => "^",
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Overflow {
+    pub day: u8,
+    pub op: Op,
+    pub lhs: i128,
+    pub rhs: i128,
+}
+
+impl std::fmt::Display for Overflow {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "day {}: overflow in {} {} {}",
+            self.day, self.lhs, self.op, self.rhs
+        )
+    }
+}
+
+impl std::error::Error for Overflow {}
+
+pub trait Integer:
+    Copy + std::ops::Add<Output = Self> + std::ops::Sub<Output = Self> + std::ops::Mul<Output = Self>
+{
+    const ZERO: Self;
+    const ONE: Self;
+
+    fn checked_add(self, rhs: Self) -> Option<Self>;
+    fn checked_sub(self, rhs: Self) -> Option<Self>;
+    fn checked_mul(self, rhs: Self) -> Option<Self>;
+    fn checked_pow(self, exp: u32) -> Option<Self>;
+    fn pow(self, exp: u32) -> Self;
+    fn widen(self) -> i128;
+}
+
+macro_rules! impl_integer {
+    ($($ty:ty),*) => {
+        $(
+            impl Integer for $ty {
+                const ZERO: Self = 0;
+                const ONE: Self = 1;
+
+                fn checked_add(self, rhs: Self) -> Option<Self> {
+                    <$ty>::checked_add(self, rhs)
+                }
+
+                fn checked_sub(self, rhs: Self) -> Option<Self> {
+                    <$ty>::checked_sub(self, rhs)
+                }
+
+                fn checked_mul(self, rhs: Self) -> Option<Self> {
+                    <$ty>::checked_mul(self, rhs)
+                }
+
+                fn checked_pow(self, exp: u32) -> Option<Self> {
+                    <$ty>::checked_pow(self, exp)
+                }
+
+                fn pow(self, exp: u32) -> Self {
+                    <$ty>::pow(self, exp)
+                }
+
+                fn widen(self) -> i128 {
+                    self as i128
+                }
+            }
+        )*
+    };
+}
+
+impl_integer!(i32, i64, u32, u64, usize);
+
+fn apply<T: Integer>(
+    day: u8,
+    op: Op,
+    (lhs, rhs): (T, i128),
+    checked: impl FnOnce() -> Option<T>,
+    plain: impl FnOnce() -> T,
+) -> Result<T, Overflow> {
+    if cfg!(feature = "checked") {
+        checked().ok_or(Overflow {
+            day,
+            op,
+            lhs: lhs.widen(),
+            rhs,
+        })
+    } else {
+        Ok(plain())
+    }
+}
+
+pub fn add<T: Integer>(day: u8, lhs: T, rhs: T) -> Result<T, Overflow> {
+    apply(
+        day,
+        Op::Add,
+        (lhs, rhs.widen()),
+        || lhs.checked_add(rhs),
+        || lhs + rhs,
+    )
+}
+
+pub fn sub<T: Integer>(day: u8, lhs: T, rhs: T) -> Result<T, Overflow> {
+    apply(
+        day,
+        Op::Sub,
+        (lhs, rhs.widen()),
+        || lhs.checked_sub(rhs),
+        || lhs - rhs,
+    )
+}
+
+pub fn mul<T: Integer>(day: u8, lhs: T, rhs: T) -> Result<T, Overflow> {
+    apply(
+        day,
+        Op::Mul,
+        (lhs, rhs.widen()),
+        || lhs.checked_mul(rhs),
+        || lhs * rhs,
+    )
+}
+
+pub fn pow<T: Integer>(day: u8, base: T, exp: u32) -> Result<T, Overflow> {
+    apply(
+        day,
+        Op::Pow,
+        (base, exp as i128),
+        || base.checked_pow(exp),
+        || base.pow(exp),
+    )
+}
+
+pub fn sum<T: Integer>(day: u8, iter: impl IntoIterator<Item = T>) -> Result<T, Overflow> {
+    iter.into_iter()
+        .try_fold(T::ZERO, |acc, value| add(day, acc, value))
+}
+
+pub fn product<T: Integer>(day: u8, iter: impl IntoIterator<Item = T>) -> Result<T, Overflow> {
+    iter.into_iter()
+        .try_fold(T::ONE, |acc, value| mul(day, acc, value))
+}
+
+/// Sums an iterator of fallible values, stopping at the first error.
+pub fn try_sum<T: Integer, E: From<Overflow>>(
+    day: u8,
+    iter: impl IntoIterator<Item = Result<T, E>>,
+) -> Result<T, E> {
+    iter.into_iter()
+        .try_fold(T::ZERO, |acc, value| Ok(add(day, acc, value?)?))
+}
+
+/// Multiplies an iterator of fallible values, stopping at the first error.
+pub fn try_product<T: Integer, E: From<Overflow>>(
+    day: u8,
+    iter: impl IntoIterator<Item = Result<T, E>>,
+) -> Result<T, E> {
+    iter.into_iter()
+        .try_fold(T::ONE, |acc, value| Ok(mul(day, acc, value?)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_in_range() {
+        assert_eq!(add(0, 2, 3), Ok(5));
+        assert_eq!(sub(0, 2i64, 3), Ok(-1));
+        assert_eq!(mul(0, 4u32, 5), Ok(20));
+        assert_eq!(pow(0, 2usize, 10), Ok(1024));
+        assert_eq!(sum(0, [1, 2, 3]), Ok(6));
+        assert_eq!(product(0, [2, 3, 4]), Ok(24));
+        assert_eq!(try_product(0, [Ok::<_, Overflow>(2), Ok(5)]), Ok(10));
+    }
+
+    #[cfg(feature = "checked")]
+    #[test]
+    fn test_overflow() {
+        let overflow = |op, lhs, rhs| Overflow {
+            day: 9,
+            op,
+            lhs,
+            rhs,
+        };
+
+        assert_eq!(
+            add(9, i32::MAX, 1),
+            Err(overflow(Op::Add, i32::MAX.into(), 1))
+        );
+        assert_eq!(sub(9, 0u32, 1), Err(overflow(Op::Sub, 0, 1)));
+        assert_eq!(
+            mul(9, i64::MIN, -1),
+            Err(overflow(Op::Mul, i64::MIN.into(), -1))
+        );
+        assert_eq!(pow(9, 2i32, 31), Err(overflow(Op::Pow, 2, 31)));
+        assert_eq!(
+            sum(9, [u64::MAX, 1]),
+            Err(overflow(Op::Add, u64::MAX.into(), 1))
+        );
+        assert_eq!(
+            try_sum(9, [Ok(i32::MAX), Ok::<_, Overflow>(1)]),
+            Err(overflow(Op::Add, i32::MAX.into(), 1))
+        );
+        assert_eq!(
+            overflow(Op::Mul, 3, 4).to_string(),
+            "day 9: overflow in 3 * 4"
+        );
+    }
+}
@@ -1,3 +1,8 @@
+use aoc::automaton::{Automaton, Match};
+use aoc::{checked, Error};
+
+const DAY: u8 = 1;
+
 #[derive(Debug)]
 struct State {
     lines: Vec<String>,
@@ -11,81 +16,600 @@ impl<D: ToString> std::iter::FromIterator<D> for State {
     }
 }
 
-fn parse_digits_helper(
-    line: &str,
-    mappings: &[(&str, i32)],
-    range: impl Fn(usize) -> std::ops::Range<usize>,
-    iter: impl Iterator<Item = (usize, char)>,
-) -> Option<i32> {
-    for (idx, c) in iter {
-        let r = range(idx);
+const ENGLISH: &[(&str, i32)] = &[
+    ("zero", 0),
+    ("one", 1),
+    ("two", 2),
+    ("three", 3),
+    ("four", 4),
+    ("five", 5),
+    ("six", 6),
+    ("seven", 7),
+    ("eight", 8),
+    ("nine", 9),
+];
+
+const TEENS: &[(&str, i32)] = &[
+    ("ten", 10),
+    ("eleven", 11),
+    ("twelve", 12),
+    ("thirteen", 13),
+    ("fourteen", 14),
+    ("fifteen", 15),
+    ("sixteen", 16),
+    ("seventeen", 17),
+    ("eighteen", 18),
+    ("nineteen", 19),
+];
+
+const TENS: &[(&str, i32)] = &[
+    ("twenty", 20),
+    ("thirty", 30),
+    ("forty", 40),
+    ("fifty", 50),
+    ("sixty", 60),
+    ("seventy", 70),
+    ("eighty", 80),
+    ("ninety", 90),
+];
+
+const FRENCH: &[(&str, i32)] = &[
+    ("zéro", 0),
+    ("un", 1),
+    ("deux", 2),
+    ("trois", 3),
+    ("quatre", 4),
+    ("cinq", 5),
+    ("six", 6),
+    ("sept", 7),
+    ("huit", 8),
+    ("neuf", 9),
+];
+
+const GERMAN: &[(&str, i32)] = &[
+    ("null", 0),
+    ("eins", 1),
+    ("zwei", 2),
+    ("drei", 3),
+    ("vier", 4),
+    ("fünf", 5),
+    ("sechs", 6),
+    ("sieben", 7),
+    ("acht", 8),
+    ("neun", 9),
+];
+
+const SPANISH: &[(&str, i32)] = &[
+    ("cero", 0),
+    ("uno", 1),
+    ("dos", 2),
+    ("tres", 3),
+    ("cuatro", 4),
+    ("cinco", 5),
+    ("seis", 6),
+    ("siete", 7),
+    ("ocho", 8),
+    ("nueve", 9),
+];
+
+/// How far spelled-out English numbers reach beyond single digits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Compounds {
+    /// Teens, tens and tens plus units, e.g. "seventeen" or "fortytwo".
+    Tens,
+    /// Everything up to 999, e.g. "onehundred" or "twohundredfortytwo".
+    Hundreds,
+}
+
+impl std::str::FromStr for Compounds {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "tens" => Ok(Self::Tens),
+            "hundreds" => Ok(Self::Hundreds),
+            _ => Err(format!("invalid compounds `{s}`")),
+        }
+    }
+}
+
+/// How tokens that share bytes, like `two` and `one` in `twone`, are read.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+enum Overlap {
+    /// Every token counts, so `twone` reads as `two` then `one`.
+    #[default]
+    Overlapping,
+    /// Tokens are replaced left to right and consume their bytes, so `twone`
+    /// reads as `two` followed by `ne`.
+    Replace,
+    /// Longer tokens are taken before shorter ones and consume their bytes,
+    /// so `oneight` reads as `on` followed by `eight`. Equally long tokens go
+    /// left to right.
+    Longest,
+}
+
+impl Overlap {
+    const ALL: [Overlap; 3] = [Overlap::Overlapping, Overlap::Replace, Overlap::Longest];
+}
+
+impl std::str::FromStr for Overlap {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "overlapping" => Ok(Self::Overlapping),
+            "replace" => Ok(Self::Replace),
+            "longest" => Ok(Self::Longest),
+            _ => Err(format!("invalid overlap `{s}`")),
+        }
+    }
+}
+
+impl std::fmt::Display for Overlap {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Overlap::Overlapping => "overlapping",
+            Overlap::Replace => "replace",
+            Overlap::Longest => "longest",
+        })
+    }
+}
+
+/// The spelled-out words recognized next to plain digits.
+#[derive(Debug, Clone, Default)]
+struct Vocabulary {
+    words: Vec<(String, i32)>,
+    ignore_case: bool,
+    overlap: Overlap,
+}
+
+impl Vocabulary {
+    fn from_table(table: &[(&str, i32)]) -> Self {
+        Self {
+            words: table
+                .iter()
+                .map(|(word, value)| (word.to_string(), *value))
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    fn english() -> Self {
+        Self::from_table(ENGLISH)
+    }
+
+    /// Adds compound English numbers as multi-digit tokens.
+    fn with_compounds(mut self, compounds: Compounds) -> Self {
+        let units = &ENGLISH[1..];
+        let below_hundred = units
+            .iter()
+            .chain(TEENS)
+            .map(|(word, value)| (word.to_string(), *value))
+            .chain(TENS.iter().flat_map(|(tens, tens_value)| {
+                std::iter::once((tens.to_string(), *tens_value)).chain(
+                    units
+                        .iter()
+                        .map(move |(unit, value)| (format!("{tens}{unit}"), tens_value + value)),
+                )
+            }))
+            .collect::<Vec<_>>();
+
+        self.words
+            .extend(below_hundred[units.len()..].iter().cloned());
+
+        if compounds == Compounds::Hundreds {
+            for (unit, value) in units {
+                let hundreds = format!("{unit}hundred");
+                self.words.push((hundreds.clone(), value * 100));
+                self.words
+                    .extend(below_hundred.iter().map(|(rest, rest_value)| {
+                        (format!("{hundreds}{rest}"), value * 100 + rest_value)
+                    }));
+            }
+        }
+
+        self
+    }
+
+    fn builtin(name: &str) -> Option<Self> {
+        let table = match name.to_ascii_lowercase().as_str() {
+            "en" | "english" => ENGLISH,
+            "fr" | "french" => FRENCH,
+            "de" | "german" => GERMAN,
+            "es" | "spanish" => SPANISH,
+            _ => return None,
+        };
+
+        Some(Self::from_table(table))
+    }
+
+    /// Parses a custom table with one `word=digit` pair per line. Blank lines
+    /// and lines starting with `#` are ignored.
+    fn parse(contents: &str) -> Result<Self, String> {
+        let words = contents
+            .lines()
+            .enumerate()
+            .map(|(idx, line)| (idx, line.trim()))
+            .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+            .map(|(idx, line)| {
+                let invalid = || format!("invalid vocabulary entry on line {}", idx + 1);
+
+                let (word, value) = line.split_once('=').ok_or_else(invalid)?;
+                let word = word.trim();
+                let value = value.trim().parse::<i32>().map_err(|_| invalid())?;
+                if word.is_empty() || !(0..10).contains(&value) {
+                    return Err(invalid());
+                }
+
+                Ok((word.to_string(), value))
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self {
+            words,
+            ..Default::default()
+        })
+    }
+
+    /// Reads `--vocabulary NAME`, `--vocabulary-file PATH`, `--compounds
+    /// tens|hundreds`, `--ignore-case` and `--overlap
+    /// overlapping|replace|longest`.
+    fn from_args(args: &mut aoc::Args) -> Result<Self, String> {
+        let name = args.value("vocabulary")?;
+        let compounds = args
+            .value("compounds")?
+            .map(|compounds| compounds.parse::<Compounds>())
+            .transpose()?;
 
-        for mapping in mappings {
-            if line[r.clone()].contains(mapping.0) {
-                return Some(mapping.1);
+        let mut vocabulary = match (name, args.value("vocabulary-file")?) {
+            (Some(_), Some(_)) => {
+                return Err("--vocabulary conflicts with --vocabulary-file".to_string())
             }
+            (Some(name), None) => {
+                Self::builtin(&name).ok_or_else(|| format!("unknown vocabulary `{name}`"))?
+            }
+            (None, Some(path)) => std::fs::read_to_string(&path)
+                .map_err(|err| format!("cannot read {path}: {err}"))
+                .and_then(|contents| Self::parse(&contents))?,
+            (None, None) => Self::english(),
+        };
+
+        if let Some(compounds) = compounds {
+            if vocabulary.words != Self::english().words {
+                return Err("--compounds requires the English vocabulary".to_string());
+            }
+            vocabulary = vocabulary.with_compounds(compounds);
         }
 
-        if c.is_numeric() {
-            return Some(c.to_digit(10)? as i32);
+        vocabulary.ignore_case = args.flag("ignore-case");
+        if let Some(overlap) = args.value("overlap")? {
+            vocabulary.overlap = overlap.parse()?;
         }
+
+        Ok(vocabulary)
     }
+}
 
-    None
+/// Folds a char for case-insensitive matching. Folding char by char keeps
+/// words and lines consistent, which `str::to_lowercase` does not for the
+/// Greek final sigma.
+fn fold(c: char) -> impl Iterator<Item = char> {
+    c.to_lowercase()
+        .map(|lower| if lower == 'ς' { 'σ' } else { lower })
 }
 
-fn parse_digits(line: &str, mappings: &[(&str, i32)]) -> Option<(i32, i32)> {
-    let first_digit =
-        parse_digits_helper(line, mappings, |idx| 0..idx + 1, line.chars().enumerate())?;
-    let last_digit = parse_digits_helper(
-        line,
-        mappings,
-        |idx| line.len() - idx..line.len(),
-        line.chars().rev().enumerate(),
-    )?;
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Digit,
+    Word,
+}
 
-    Some((first_digit, last_digit))
+impl std::fmt::Display for Kind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Kind::Digit => "digit",
+            Kind::Word => "word",
+        })
+    }
 }
 
-fn part1(state: &State) -> i32 {
-    state
-        .lines
-        .iter()
-        .filter_map(|line| parse_digits(line, &[]).map(|(first, last)| first * 10 + last))
-        .sum()
+/// A matched digit or word, with byte offsets into the original line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Token {
+    start: usize,
+    end: usize,
+    kind: Kind,
+    value: i32,
+}
+
+/// Finds the first and last digit of a line, written as a digit or spelled
+/// out, with a single forward pass and a single reverse pass.
+///
+/// Lines may hold any UTF-8. Matching works on bytes, and since every pattern
+/// is itself UTF-8, matches always start and end on char boundaries. Only the
+/// ASCII digits count as digits; other numeric chars such as `٣` or `½` are
+/// skipped unless the vocabulary spells them out.
+///
+/// By default tokens may overlap: the first token is the match that starts
+/// first and the last token is the match that ends last; ties go to the
+/// longest match, so words sharing a prefix resolve to the longer one. Other
+/// readings are picked with [`Overlap`]. The first digit is the
+/// leading digit of the first token and the last digit is the trailing digit
+/// of the last token:
+///
+/// - `eightwo` reads as `eight` then `two`, giving 82.
+/// - `twentyone` is a single token with compounds enabled, giving 21; without
+///   them only `one` matches, giving 11.
+/// - `fourteen` is 14 with compounds enabled and 44 without.
+struct Scanner {
+    forward: Automaton<i32>,
+    reverse: Automaton<i32>,
+    ignore_case: bool,
+    overlap: Overlap,
 }
 
-fn part2(state: &State) -> i32 {
+impl Scanner {
+    fn new(vocabulary: &Vocabulary) -> Self {
+        let patterns = (0..10)
+            .map(|digit| (digit.to_string(), digit))
+            .chain(vocabulary.words.iter().map(|(word, value)| {
+                if vocabulary.ignore_case {
+                    (word.chars().flat_map(fold).collect(), *value)
+                } else {
+                    (word.clone(), *value)
+                }
+            }))
+            .collect::<Vec<_>>();
+
+        Self {
+            forward: Automaton::new(
+                patterns
+                    .iter()
+                    .map(|(pattern, value)| (pattern.as_bytes(), *value)),
+            ),
+            reverse: Automaton::new(
+                patterns
+                    .iter()
+                    .map(|(pattern, value)| (pattern.bytes().rev().collect::<Vec<_>>(), *value)),
+            ),
+            ignore_case: vocabulary.ignore_case,
+            overlap: vocabulary.overlap,
+        }
+    }
+
+    /// Finds the first and last token of a line.
+    fn tokens(&self, line: &str) -> Option<(Token, Token)> {
+        // Lowercasing can change the length of a char, so remember which
+        // original char each folded byte came from.
+        let mut spans = Vec::new();
+        let mut folded = String::new();
+        let haystack = if self.ignore_case {
+            for (idx, c) in line.char_indices() {
+                for lower in fold(c) {
+                    folded.push(lower);
+                    spans.extend(std::iter::repeat_n(
+                        idx..idx + c.len_utf8(),
+                        lower.len_utf8(),
+                    ));
+                }
+            }
+            &folded
+        } else {
+            line
+        };
+
+        let token = |start: usize, end: usize, value: i32| {
+            let (start, end) = if self.ignore_case {
+                (spans[start].start, spans[end - 1].end)
+            } else {
+                (start, end)
+            };
+            let kind = if line[start..end].bytes().all(|b| b.is_ascii_digit()) {
+                Kind::Digit
+            } else {
+                Kind::Word
+            };
+
+            Token {
+                start,
+                end,
+                kind,
+                value,
+            }
+        };
+
+        if self.overlap != Overlap::Overlapping {
+            let (first, last) = self.consume(haystack.as_bytes())?;
+            return Some((
+                token(first.start, first.end, *first.value),
+                token(last.start, last.end, *last.value),
+            ));
+        }
+
+        let first = self.forward.leftmost(haystack.as_bytes())?;
+        let first = token(first.start, first.end, *first.value);
+
+        let reversed = haystack.bytes().rev().collect::<Vec<_>>();
+        let last = self.reverse.leftmost(&reversed)?;
+        let last = token(
+            haystack.len() - last.end,
+            haystack.len() - last.start,
+            *last.value,
+        );
+
+        Some((first, last))
+    }
+
+    /// Picks non-overlapping tokens in the order given by `self.overlap` and
+    /// returns the first and last of them.
+    fn consume<'a>(&'a self, haystack: &'a [u8]) -> Option<(Match<'a, i32>, Match<'a, i32>)> {
+        let mut matches = self.forward.find_overlapping(haystack).collect::<Vec<_>>();
+        match self.overlap {
+            Overlap::Longest => {
+                matches.sort_by_key(|found| (std::cmp::Reverse(found.len()), found.start))
+            }
+            _ => matches.sort_by_key(|found| (found.start, std::cmp::Reverse(found.len()))),
+        }
+
+        // Taken tokens keyed by start, to check a candidate against its
+        // neighbours only.
+        let mut taken = std::collections::BTreeMap::<usize, Match<i32>>::new();
+        for found in matches {
+            let clashes = taken
+                .range(..found.end)
+                .next_back()
+                .is_some_and(|(_, before)| before.end > found.start);
+            if !clashes {
+                taken.insert(found.start, found);
+            }
+        }
+
+        let first = *taken.first_key_value()?.1;
+        let last = *taken.last_key_value()?.1;
+
+        Some((first, last))
+    }
+
+    fn parse_digits(&self, line: &str) -> Option<(i32, i32)> {
+        let (first, last) = self.tokens(line)?;
+
+        aoc::debug!("{line:?}: first {}, last {}", first.value, last.value);
+
+        Some((leading_digit(first.value), last.value % 10))
+    }
+}
+
+fn leading_digit(mut value: i32) -> i32 {
+    while value >= 10 {
+        value /= 10;
+    }
+    value
+}
+
+fn calibration_value((first, last): (i32, i32)) -> Result<i32, Error> {
+    Ok(checked::add(DAY, checked::mul(DAY, first, 10)?, last)?)
+}
+
+fn calibrate(state: &State, vocabulary: &Vocabulary) -> Result<i32, Error> {
+    let scanner = Scanner::new(vocabulary);
+
+    checked::try_sum(
+        DAY,
+        state
+            .lines
+            .iter()
+            .filter_map(|line| scanner.parse_digits(line).map(calibration_value)),
+    )
+}
+
+/// Describes how each line was calibrated, flagging lines without a digit.
+fn explain(state: &State, vocabulary: &Vocabulary) -> String {
+    let scanner = Scanner::new(vocabulary);
+    let describe = |line: &str, token: Token| {
+        format!(
+            "{:?} ({} at byte {}) = {}",
+            &line[token.start..token.end],
+            token.kind,
+            token.start,
+            token.value
+        )
+    };
+
     state
         .lines
         .iter()
-        .filter_map(|line| {
-            parse_digits(
-                line,
-                &[
-                    ("zero", 0),
-                    ("one", 1),
-                    ("two", 2),
-                    ("three", 3),
-                    ("four", 4),
-                    ("five", 5),
-                    ("six", 6),
-                    ("seven", 7),
-                    ("eight", 8),
-                    ("nine", 9),
-                ],
-            )
-            .map(|(first, last)| first * 10 + last)
+        .enumerate()
+        .map(|(idx, line)| match scanner.tokens(line) {
+            Some((first, last)) => {
+                let value = calibration_value((leading_digit(first.value), last.value % 10))
+                    .map_or_else(|err| err.to_string(), |value| value.to_string());
+
+                format!(
+                    "line {}: first {}, last {}, value {value}",
+                    idx + 1,
+                    describe(line, first),
+                    describe(line, last),
+                )
+            }
+            None => format!("line {}: no digit in {line:?}", idx + 1),
         })
-        .sum()
+        .collect::<Vec<_>>()
+        .join("\n")
 }
 
-fn main() {
-    let state = aoc::get_input();
+/// Lists the lines whose calibration value depends on the [`Overlap`] policy.
+fn compare_overlaps(state: &State, vocabulary: &Vocabulary) -> Result<String, Error> {
+    let scanners = Overlap::ALL.map(|overlap| {
+        Scanner::new(&Vocabulary {
+            overlap,
+            ..vocabulary.clone()
+        })
+    });
+
+    let mut report = Vec::new();
+    for (idx, line) in state.lines.iter().enumerate() {
+        let mut values = [None; Overlap::ALL.len()];
+        for (value, scanner) in values.iter_mut().zip(&scanners) {
+            *value = scanner
+                .parse_digits(line)
+                .map(calibration_value)
+                .transpose()?;
+        }
+        if values.iter().all(|value| *value == values[0]) {
+            continue;
+        }
+
+        let values = Overlap::ALL
+            .iter()
+            .zip(values)
+            .map(|(overlap, value)| match value {
+                Some(value) => format!("{overlap} {value}"),
+                None => format!("{overlap} none"),
+            })
+            .collect::<Vec<_>>();
+
+        report.push(format!("line {} {line:?}: {}", idx + 1, values.join(", ")));
+    }
+
+    Ok(report.join("\n"))
+}
 
-    println!("part 1: {}", part1(&state));
+fn part1(state: &State) -> Result<i32, Error> {
+    calibrate(state, &Vocabulary::default())
+}
 
-    println!("part 2: {}", part2(&state));
+fn part2(state: &State, vocabulary: &Vocabulary) -> Result<i32, Error> {
+    calibrate(state, vocabulary)
+}
+
+fn main() -> std::process::ExitCode {
+    let mut args = aoc::Args::from_env();
+    let vocabulary = match Vocabulary::from_args(&mut args) {
+        Ok(vocabulary) => vocabulary,
+        Err(err) => return aoc::runner::usage_error(DAY, err),
+    };
+
+    let parse = |lines: Vec<String>| Ok(lines.into_iter().collect::<State>());
+
+    if args.flag("compare-overlaps") {
+        return aoc::runner::run_mode(DAY, args, parse, move |state: &State| {
+            compare_overlaps(state, &vocabulary)
+        });
+    }
+
+    if args.flag("explain") {
+        return aoc::runner::run_mode(DAY, args, parse, move |state: &State| {
+            Ok::<_, Error>(format!(
+                "part 1:\n{}\npart 2:\n{}",
+                explain(state, &Vocabulary::default()),
+                explain(state, &vocabulary)
+            ))
+        });
+    }
+
+    aoc::runner::run_with(DAY, args, part1, move |state: &State| {
+        part2(state, &vocabulary)
+    })
 }
 
 #[cfg(test)]
@@ -100,7 +624,7 @@ mod tests {
 
         let state = aoc::get_input_from(EXAMPLE_INPUT);
 
-        assert_eq!(part1(&state), 142);
+        assert_eq!(part1(&state), Ok(142));
     }
 
     #[test]
@@ -109,20 +633,150 @@ mod tests {
 
         let state = aoc::get_input_from(EXAMPLE_INPUT);
 
-        assert_eq!(part2(&state), 281);
+        assert_eq!(part2(&state, &Vocabulary::english()), Ok(281));
+    }
+
+    #[test]
+    fn test_long_line() {
+        let line = format!("{}eightwo{}", "x".repeat(500_000), "y".repeat(500_000));
+        let state = aoc::get_input_from(&line);
+
+        assert_eq!(part2(&state, &Vocabulary::english()), Ok(82));
+        assert_eq!(part1(&state), Ok(0));
+    }
+
+    #[test]
+    fn test_vocabularies() {
+        let state = aoc::get_input_from("deuxtroisx\nzweiundvierzig\nochoycinco\nséptimo7");
+
+        let french = Vocabulary::builtin("fr").unwrap();
+        assert_eq!(part2(&state, &french), Ok(23 + 11 + 77));
+
+        let german = Vocabulary::builtin("de").unwrap();
+        assert_eq!(part2(&state, &german), Ok(24 + 77));
+
+        let spanish = Vocabulary::builtin("spanish").unwrap();
+        assert_eq!(part2(&state, &spanish), Ok(85 + 77));
+    }
+
+    #[test]
+    fn test_custom_vocabulary() {
+        let mut vocabulary = Vocabulary::parse("# prefixes\nsi=1\nsix=6\nsixty=9\n").unwrap();
+        let state = aoc::get_input_from("xsixtyx\nSIXsi");
+
+        assert_eq!(part2(&state, &vocabulary), Ok(99 + 11));
+
+        vocabulary.ignore_case = true;
+        assert_eq!(part2(&state, &vocabulary), Ok(99 + 61));
+
+        assert!(Vocabulary::parse("ten=10").is_err());
+        assert!(Vocabulary::parse("one").is_err());
+    }
+
+    #[test]
+    fn test_compounds() {
+        let tens = Vocabulary::english().with_compounds(Compounds::Tens);
+        let hundreds = Vocabulary::english().with_compounds(Compounds::Hundreds);
+
+        for (line, english, tens_value, hundreds_value) in [
+            ("eightwo", 82, 82, 82),
+            ("twentyone", 11, 21, 21),
+            ("xfortytwox", 22, 42, 42),
+            ("fourteen", 44, 14, 14),
+            ("onehundred", 11, 11, 10),
+            ("3twohundredfortyeightwo", 32, 32, 32),
+        ] {
+            let state = aoc::get_input_from(line);
+
+            assert_eq!(part2(&state, &Vocabulary::english()), Ok(english), "{line}");
+            assert_eq!(part2(&state, &tens), Ok(tens_value), "{line}");
+            assert_eq!(part2(&state, &hundreds), Ok(hundreds_value), "{line}");
+        }
+    }
+
+    #[test]
+    fn test_explain() {
+        let state = aoc::get_input_from("two1nine\nabc\nxEIGHTwo");
+        let mut vocabulary = Vocabulary::english();
+        vocabulary.ignore_case = true;
+
+        assert_eq!(
+            explain(&state, &vocabulary),
+            [
+                r#"line 1: first "two" (word at byte 0) = 2, last "nine" (word at byte 4) = 9, value 29"#,
+                r#"line 2: no digit in "abc""#,
+                r#"line 3: first "EIGHT" (word at byte 1) = 8, last "Two" (word at byte 5) = 2, value 82"#,
+            ]
+            .join("\n")
+        );
+        assert!(explain(&state, &Vocabulary::default())
+            .starts_with(r#"line 1: first "1" (digit at byte 3) = 1, last "1" (digit at byte 3)"#));
+    }
+
+    #[test]
+    fn test_unicode() {
+        let state = aoc::get_input_from("٣fünf½sechs٤\nzweiß\nİ9ß");
+        let german = Vocabulary::builtin("de").unwrap();
+
+        assert_eq!(part1(&state), Ok(99));
+        assert_eq!(part2(&state, &german), Ok(56 + 22 + 99));
+
+        let mut greek = Vocabulary::parse("ένα=1\nτρεις=3").unwrap();
+        greek.ignore_case = true;
+        let scanner = Scanner::new(&greek);
+
+        let line = "İxΈΝΑ·ΤΡΕΙΣ";
+        let (first, last) = scanner.tokens(line).unwrap();
+        assert_eq!(&line[first.start..first.end], "ΈΝΑ");
+        assert_eq!(&line[last.start..last.end], "ΤΡΕΙΣ");
+        assert_eq!((first.kind, last.kind), (Kind::Word, Kind::Word));
+        assert_eq!(scanner.parse_digits(line), Some((1, 3)));
+    }
+
+    #[test]
+    fn test_overlap() {
+        let scanner = |overlap, compounds: Option<Compounds>| {
+            let mut vocabulary = Vocabulary::english();
+            if let Some(compounds) = compounds {
+                vocabulary = vocabulary.with_compounds(compounds);
+            }
+            vocabulary.overlap = overlap;
+            Scanner::new(&vocabulary)
+        };
+
+        for (line, compounds, expected) in [
+            ("twone", None, [(2, 1), (2, 2), (2, 2)]),
+            ("eighthree", None, [(8, 3), (8, 8), (8, 8)]),
+            ("oneight", None, [(1, 8), (1, 1), (8, 8)]),
+            (
+                "xsevenineteen",
+                Some(Compounds::Tens),
+                [(7, 9), (7, 7), (1, 9)],
+            ),
+        ] {
+            let found =
+                Overlap::ALL.map(|overlap| scanner(overlap, compounds).parse_digits(line).unwrap());
+            assert_eq!(found, expected, "{line}");
+        }
+
+        let state = aoc::get_input_from("two1nine\ntwone");
+        assert_eq!(
+            compare_overlaps(&state, &Vocabulary::english()),
+            Ok(r#"line 2 "twone": overlapping 21, replace 22, longest 22"#.to_owned())
+        );
     }
 
     #[test]
     fn test_part_1() {
         let state = aoc::get_input_from(INPUT);
 
-        assert_eq!(part1(&state), 53080);
+        assert_eq!(part1(&state), Ok(53080));
     }
 
     #[test]
     fn test_part_2() {
         let state = aoc::get_input_from(INPUT);
 
-        assert_eq!(part2(&state), 53268);
+        assert_eq!(part2(&state, &Vocabulary::english()), Ok(53268));
     }
 }
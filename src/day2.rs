@@ -1,59 +1,177 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use aoc::rng::Rng;
+use aoc::{checked, Error, Overflow};
+
+const DAY: u8 = 2;
+
 #[derive(Debug)]
 struct State {
     games: Vec<Game>,
+    /// Every color seen in any game.
+    palette: BTreeSet<String>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 struct Game {
     idx: i32,
     sets: Vec<Set>,
 }
 
-#[derive(Debug)]
+/// Cube counts by color. Colors that are not listed count as zero.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
 struct Set {
-    red: i32,
-    green: i32,
-    blue: i32,
+    counts: BTreeMap<String, i32>,
+}
+
+/// The cubes in the bag by color, e.g. `red=12,green=13,blue=14`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Bag {
+    counts: BTreeMap<String, i32>,
+}
+
+impl Default for Bag {
+    fn default() -> Self {
+        Self {
+            counts: [("red", 12), ("green", 13), ("blue", 14)]
+                .into_iter()
+                .map(|(color, count)| (color.to_string(), count))
+                .collect(),
+        }
+    }
+}
+
+impl Bag {
+    fn count(&self, color: &str) -> i32 {
+        self.counts.get(color).copied().unwrap_or_default()
+    }
+
+    fn total(&self) -> Result<i32, Overflow> {
+        checked::sum(DAY, self.counts.values().copied())
+    }
+
+    /// Whether `set` could have been drawn from this bag.
+    fn holds(&self, set: &Set) -> bool {
+        set.counts
+            .iter()
+            .all(|(color, count)| *count <= self.count(color))
+    }
+
+    /// Reads `--bag COLOR=COUNT,...`, defaulting to 12 red, 13 green and 14
+    /// blue cubes.
+    fn from_args(args: &mut aoc::Args) -> Result<Self, String> {
+        args.value("bag")?
+            .map_or_else(|| Ok(Self::default()), |bag| bag.parse())
+    }
+}
+
+impl std::str::FromStr for Bag {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let counts = s
+            .split(',')
+            .map(|entry| {
+                let invalid = || format!("invalid bag entry `{}`", entry.trim());
+
+                let (color, count) = entry.split_once('=').ok_or_else(invalid)?;
+                let color = color.trim();
+                let count = count.trim().parse::<i32>().map_err(|_| invalid())?;
+                if color.is_empty() || count < 0 {
+                    return Err(invalid());
+                }
+
+                Ok((color.to_string(), count))
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self { counts })
+    }
+}
+
+impl std::fmt::Display for Bag {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (idx, (color, count)) in self.counts.iter().enumerate() {
+            if idx > 0 {
+                f.write_str(",")?;
+            }
+            write!(f, "{color}={count}")?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+enum ParseError {
+    Malformed,
+    Overflow(Overflow),
+}
+
+impl std::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Malformed => f.write_str("malformed game"),
+            Self::Overflow(err) => err.fmt(f),
+        }
+    }
+}
+
+impl From<Overflow> for ParseError {
+    fn from(err: Overflow) -> Self {
+        Self::Overflow(err)
+    }
+}
+
+/// Whether `color` can be written back out in the puzzle format.
+fn is_color(color: &str) -> bool {
+    !color.is_empty() && !color.contains(|c: char| c.is_whitespace() || c == ',' || c == ';')
 }
 
 impl std::str::FromStr for Set {
-    type Err = ();
+    type Err = ParseError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let mut red = 0;
-        let mut green = 0;
-        let mut blue = 0;
+        let mut counts = BTreeMap::<String, i32>::new();
 
         let colors = s.split(',');
         for color in colors {
             let mut color = color.trim().splitn(2, ' ');
-            let count = color.next().ok_or(())?.parse::<i32>().map_err(|_| ())?;
-            let color = color.next().ok_or(())?.trim();
-            match color {
-                "red" => red += count,
-                "green" => green += count,
-                "blue" => blue += count,
-                _ => return Err(()),
+            let count = color
+                .next()
+                .ok_or(ParseError::Malformed)?
+                .parse::<i32>()
+                .map_err(|_| ParseError::Malformed)?;
+            let color = color.next().ok_or(ParseError::Malformed)?.trim();
+            if !is_color(color) {
+                return Err(ParseError::Malformed);
             }
+
+            let total = counts.entry(color.to_string()).or_default();
+            *total = checked::add(DAY, *total, count)?;
         }
 
-        Ok(Set { red, green, blue })
+        Ok(Set { counts })
     }
 }
 
 impl std::str::FromStr for Game {
-    type Err = ();
+    type Err = ParseError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let s = s.strip_prefix("Game ").ok_or(())?;
+        let s = s.strip_prefix("Game ").ok_or(ParseError::Malformed)?;
         let mut splits = s.splitn(2, ':');
         let idx = splits
             .next()
-            .ok_or(())?
+            .ok_or(ParseError::Malformed)?
             .trim()
             .parse::<i32>()
-            .map_err(|_| ())?;
-        let sets = splits.next().ok_or(())?.trim().split(';');
+            .map_err(|_| ParseError::Malformed)?;
+        let sets = splits
+            .next()
+            .ok_or(ParseError::Malformed)?
+            .trim()
+            .split(';');
         let sets = sets
             .map(std::str::FromStr::from_str)
             .collect::<Result<_, _>>()?;
@@ -62,56 +180,1357 @@ impl std::str::FromStr for Game {
     }
 }
 
+/// Writes the set in the puzzle format with colors in alphabetical order,
+/// e.g. `3 blue, 4 red`.
+impl std::fmt::Display for Set {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (idx, (color, count)) in self.counts.iter().enumerate() {
+            if idx > 0 {
+                f.write_str(", ")?;
+            }
+            write!(f, "{count} {color}")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Writes the game in the puzzle format, e.g. `Game 1: 3 blue, 4 red; 2 green`.
+impl std::fmt::Display for Game {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Game {}:", self.idx)?;
+        for (idx, set) in self.sets.iter().enumerate() {
+            f.write_str(if idx > 0 { "; " } else { " " })?;
+            write!(f, "{set}")?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Game {
+    /// Whether every set of this game could have been drawn from `bag`.
+    fn is_feasible(&self, bag: &Bag) -> bool {
+        self.sets.iter().all(|set| bag.holds(set))
+    }
+
+    /// The smallest bag every set of this game could have been drawn from.
+    fn minimum_bag(&self) -> Bag {
+        let mut counts = BTreeMap::<String, i32>::new();
+        for (color, count) in self.sets.iter().flat_map(|set| &set.counts) {
+            let max = counts.entry(color.clone()).or_default();
+            *max = (*max).max(*count);
+        }
+
+        Bag { counts }
+    }
+}
+
 impl<D: AsRef<str>> std::iter::FromIterator<D> for State {
     fn from_iter<T: IntoIterator<Item = D>>(iter: T) -> Self {
-        Self {
-            games: iter
-                .into_iter()
-                .map(|s| s.as_ref().parse())
-                .collect::<Result<_, _>>()
-                .unwrap(),
+        Self::parse(iter).unwrap_or_else(|err| panic!("{err}"))
+    }
+}
+
+impl State {
+    fn parse<S: AsRef<str>>(lines: impl IntoIterator<Item = S>) -> Result<Self, Error> {
+        let games = lines
+            .into_iter()
+            .enumerate()
+            .map(|(idx, line)| {
+                line.as_ref().parse::<Game>().map_err(|err| match err {
+                    ParseError::Malformed => Error::Parse(format!("line {}: {err}", idx + 1)),
+                    ParseError::Overflow(err) => Error::Overflow(err),
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let palette = games
+            .iter()
+            .flat_map(|game| &game.sets)
+            .flat_map(|set| set.counts.keys().cloned())
+            .collect();
+
+        Ok(Self { games, palette })
+    }
+
+    /// The colors of the input together with those of `bag`.
+    fn palette_with(&self, bag: &Bag) -> Vec<String> {
+        self.palette
+            .iter()
+            .chain(bag.counts.keys())
+            .cloned()
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect()
+    }
+}
+
+/// A way of writing down a list of games.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Format {
+    /// The puzzle format, one game per line.
+    Text,
+    /// An array of `{"id": 1, "sets": [{"blue": 3, "red": 4}, ...]}` objects.
+    Json,
+    /// `game,set,color,count` rows with a header, sets numbered from 1. Game
+    /// ids must be unique.
+    Csv,
+}
+
+impl std::str::FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(Self::Text),
+            "json" => Ok(Self::Json),
+            "csv" => Ok(Self::Csv),
+            _ => Err(format!("invalid format `{s}`")),
+        }
+    }
+}
+
+impl Format {
+    fn read(&self, input: &str) -> Result<Vec<Game>, String> {
+        match self {
+            Format::Text => input
+                .lines()
+                .enumerate()
+                .filter(|(_, line)| !line.trim().is_empty())
+                .map(|(idx, line)| {
+                    line.parse()
+                        .map_err(|err| format!("line {}: {err}", idx + 1))
+                })
+                .collect(),
+            Format::Json => read_json(&aoc::json::Value::parse(input)?),
+            Format::Csv => read_csv(input),
+        }
+    }
+
+    fn write(&self, games: &[Game]) -> String {
+        match self {
+            Format::Text => games.iter().map(|game| format!("{game}\n")).collect(),
+            Format::Json => format!("{}\n", write_json(games)),
+            Format::Csv => std::iter::once("game,set,color,count\n".to_string())
+                .chain(games.iter().flat_map(|game| {
+                    game.sets.iter().enumerate().flat_map(move |(idx, set)| {
+                        set.counts.iter().map(move |(color, count)| {
+                            format!("{},{},{color},{count}\n", game.idx, idx + 1)
+                        })
+                    })
+                }))
+                .collect(),
         }
     }
 }
 
-fn part_1(state: &State) -> i32 {
-    state
-        .games
+fn write_json(games: &[Game]) -> aoc::json::Value {
+    use aoc::json::Value;
+
+    Value::Array(
+        games
+            .iter()
+            .map(|game| {
+                Value::Object(vec![
+                    ("id".to_string(), i64::from(game.idx).into()),
+                    (
+                        "sets".to_string(),
+                        Value::Array(
+                            game.sets
+                                .iter()
+                                .map(|set| {
+                                    Value::Object(
+                                        set.counts
+                                            .iter()
+                                            .map(|(color, count)| {
+                                                (color.clone(), i64::from(*count).into())
+                                            })
+                                            .collect(),
+                                    )
+                                })
+                                .collect(),
+                        ),
+                    ),
+                ])
+            })
+            .collect(),
+    )
+}
+
+fn read_json(value: &aoc::json::Value) -> Result<Vec<Game>, String> {
+    let int = |value: &aoc::json::Value, what: &str| {
+        value
+            .as_i64()
+            .and_then(|n| i32::try_from(n).ok())
+            .ok_or_else(|| format!("invalid {what}"))
+    };
+
+    value
+        .as_array()
+        .ok_or("expected an array of games")?
         .iter()
-        .filter_map(|game| {
-            if game
-                .sets
+        .map(|game| {
+            let idx = int(game.get("id").ok_or("missing game id")?, "game id")?;
+            let sets = game
+                .get("sets")
+                .and_then(|sets| sets.as_array())
+                .ok_or_else(|| format!("game {idx}: missing sets"))?
                 .iter()
-                .any(|set| set.red > 12 || set.green > 13 || set.blue > 14)
-            {
-                None
-            } else {
-                Some(game.idx)
-            }
+                .map(|set| {
+                    // A color repeated in a set adds up, as in the text format.
+                    let mut counts = BTreeMap::<String, i32>::new();
+                    for (color, count) in set
+                        .as_object()
+                        .ok_or_else(|| format!("game {idx}: expected a set object"))?
+                    {
+                        if !is_color(color) {
+                            return Err(format!("game {idx}: invalid color `{color}`"));
+                        }
+                        let total = counts.entry(color.clone()).or_default();
+                        *total = total
+                            .checked_add(int(count, "count")?)
+                            .ok_or_else(|| format!("game {idx}: invalid count"))?;
+                    }
+                    if counts.is_empty() {
+                        return Err(format!("game {idx}: empty set"));
+                    }
+
+                    Ok(Set { counts })
+                })
+                .collect::<Result<_, String>>()?;
+
+            Ok(Game { idx, sets })
         })
-        .sum()
+        .collect()
 }
 
-fn part_2(state: &State) -> i32 {
-    state
-        .games
+fn read_csv(input: &str) -> Result<Vec<Game>, String> {
+    let mut games = Vec::<Game>::new();
+
+    for (idx, row) in input.lines().enumerate().skip(1) {
+        if row.trim().is_empty() {
+            continue;
+        }
+        let invalid = || format!("invalid row on line {}", idx + 1);
+
+        let fields = row.split(',').map(str::trim).collect::<Vec<_>>();
+        let [game_idx, set, color, count] = fields[..] else {
+            return Err(invalid());
+        };
+        let game_idx = game_idx.parse::<i32>().map_err(|_| invalid())?;
+        let set = set.parse::<usize>().map_err(|_| invalid())?;
+        let count = count.parse::<i32>().map_err(|_| invalid())?;
+        if !is_color(color) {
+            return Err(invalid());
+        }
+
+        if games.last().is_none_or(|game| game.idx != game_idx) {
+            if games.iter().any(|game| game.idx == game_idx) {
+                return Err(format!("rows of game {game_idx} are not together"));
+            }
+            games.push(Game {
+                idx: game_idx,
+                sets: Vec::new(),
+            });
+        }
+
+        let game = games.last_mut().unwrap();
+        if set == game.sets.len() + 1 {
+            game.sets.push(Set::default());
+        } else if set != game.sets.len() {
+            return Err(format!("game {game_idx}: sets are not numbered in order"));
+        }
+
+        let total = game
+            .sets
+            .last_mut()
+            .unwrap()
+            .counts
+            .entry(color.to_string())
+            .or_default();
+        *total = total.checked_add(count).ok_or_else(invalid)?;
+    }
+
+    Ok(games)
+}
+
+/// The games that could have been played with `bag`.
+fn feasible<'a>(games: &'a [Game], bag: &'a Bag) -> impl Iterator<Item = &'a Game> + 'a {
+    games.iter().filter(|game| game.is_feasible(bag))
+}
+
+/// A depth-first search for the smallest bag making enough games feasible,
+/// choosing one color's count at a time. Each color holds either no cubes or
+/// exactly as many as some game needs, since any other bag admits the same
+/// games as one of these with fewer cubes.
+struct Search {
+    /// The cubes of each color each game needs, by palette index.
+    needs: Vec<Vec<i32>>,
+    /// The counts worth trying for each color, in increasing order.
+    values: Vec<Vec<i32>>,
+    min_games: usize,
+    chosen: Vec<i32>,
+    /// The best counts so far, with their total and feasible game count.
+    best: Option<(Vec<i32>, i32, usize)>,
+}
+
+impl Search {
+    fn new(games: &[Game], palette: &[String], min_games: usize) -> Self {
+        let needs = games
+            .iter()
+            .map(|game| {
+                let minimum = game.minimum_bag();
+                palette.iter().map(|color| minimum.count(color)).collect()
+            })
+            .collect::<Vec<Vec<_>>>();
+        let values = (0..palette.len())
+            .map(|color| {
+                std::iter::once(0)
+                    .chain(needs.iter().map(|need| need[color]))
+                    .collect::<BTreeSet<_>>()
+                    .into_iter()
+                    .collect()
+            })
+            .collect();
+
+        Self {
+            needs,
+            values,
+            min_games,
+            chosen: Vec::new(),
+            best: None,
+        }
+    }
+
+    /// Tries every count for the next color, given the games still
+    /// feasible. Counts are tried in increasing order, so the first one that
+    /// cannot beat the best bag ends the loop.
+    fn visit(&mut self, total: i32, feasible: &[usize]) -> Result<(), Error> {
+        aoc::checkpoint()?;
+
+        if feasible.len() < self.min_games {
+            return Ok(());
+        }
+
+        let color = self.chosen.len();
+        if color == self.values.len() {
+            self.best = Some((self.chosen.clone(), total, feasible.len()));
+            return Ok(());
+        }
+
+        for idx in 0..self.values[color].len() {
+            let value = self.values[color][idx];
+            let total = checked::add(DAY, total, value)?;
+            let feasible = feasible
+                .iter()
+                .copied()
+                .filter(|game| self.needs[*game][color] <= value)
+                .collect::<Vec<_>>();
+
+            if let Some((_, best, count)) = self.best {
+                if total > best || (total == best && feasible.len() <= count) {
+                    break;
+                }
+            }
+
+            self.chosen.push(value);
+            self.visit(total, &feasible)?;
+            self.chosen.pop();
+        }
+
+        Ok(())
+    }
+}
+
+/// The bag with the fewest cubes that makes at least `min_games` games
+/// feasible, with its feasible game count, or `None` if there are not that
+/// many games. Among bags of the same size the one allowing the most games
+/// wins.
+fn smallest_bag(
+    games: &[Game],
+    palette: &[String],
+    min_games: usize,
+) -> Result<Option<(Bag, usize)>, Error> {
+    let mut search = Search::new(games, palette, min_games);
+    search.visit(0, &(0..games.len()).collect::<Vec<_>>())?;
+
+    Ok(search.best.map(|(counts, _, count)| {
+        let bag = Bag {
+            counts: palette.iter().cloned().zip(counts).collect(),
+        };
+        (bag, count)
+    }))
+}
+
+/// The bag with the fewest cubes that makes every game in `ids` feasible.
+fn smallest_bag_for(
+    games: &[Game],
+    palette: &[String],
+    ids: &BTreeSet<i32>,
+) -> Result<Bag, String> {
+    if let Some(id) = ids
         .iter()
-        .filter_map(|game| {
-            let red = game.sets.iter().map(|set| set.red).max()?;
-            let green = game.sets.iter().map(|set| set.green).max()?;
-            let blue = game.sets.iter().map(|set| set.blue).max()?;
+        .find(|id| !games.iter().any(|game| game.idx == **id))
+    {
+        return Err(format!("no game {id}"));
+    }
+
+    let mut bag = Bag {
+        counts: palette.iter().map(|color| (color.clone(), 0)).collect(),
+    };
+    for game in games.iter().filter(|game| ids.contains(&game.idx)) {
+        for (color, count) in game.minimum_bag().counts {
+            let max = bag.counts.entry(color).or_default();
+            *max = (*max).max(count);
+        }
+    }
+
+    Ok(bag)
+}
+
+/// The bags that no other bag beats on both total cubes and feasible games,
+/// with their feasible game counts, by increasing total.
+fn pareto_frontier(games: &[Game], palette: &[String]) -> Result<Vec<(Bag, usize)>, Error> {
+    let mut frontier = Vec::new();
+    let mut min_games = 0;
+    while let Some((bag, count)) = smallest_bag(games, palette, min_games)? {
+        min_games = count + 1;
+        frontier.push((bag, count));
+    }
 
-            Some(red * green * blue)
+    Ok(frontier)
+}
+
+/// A "what if" question about the bag, asked from the command line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Query {
+    /// `--feasible`: the games feasible for the bag.
+    Feasible,
+    /// `--min-games N`: the smallest bag making at least N games feasible.
+    MinGames(usize),
+    /// `--games ID,...`: the smallest bag making these games feasible.
+    Games(BTreeSet<i32>),
+    /// `--frontier`: the Pareto frontier of bag size against feasible games.
+    Frontier,
+}
+
+impl Query {
+    fn from_args(args: &mut aoc::Args) -> Result<Vec<Self>, String> {
+        let mut queries = Vec::new();
+
+        if args.flag("feasible") {
+            queries.push(Self::Feasible);
+        }
+        if let Some(min_games) = args.value("min-games")? {
+            let min_games = min_games
+                .parse()
+                .map_err(|_| format!("invalid game count `{min_games}`"))?;
+            queries.push(Self::MinGames(min_games));
+        }
+        if let Some(ids) = args.value("games")? {
+            let ids = ids
+                .split(',')
+                .map(|id| {
+                    id.trim()
+                        .parse()
+                        .map_err(|_| format!("invalid game id `{}`", id.trim()))
+                })
+                .collect::<Result<_, _>>()?;
+            queries.push(Self::Games(ids));
+        }
+        if args.flag("frontier") {
+            queries.push(Self::Frontier);
+        }
+
+        Ok(queries)
+    }
+
+    fn answer(&self, state: &State, bag: &Bag) -> Result<String, String> {
+        let palette = state.palette_with(bag);
+        let list = |ids: &mut dyn Iterator<Item = i32>| {
+            ids.map(|id| id.to_string()).collect::<Vec<_>>().join(", ")
+        };
+
+        match self {
+            Query::Feasible => {
+                let ids = feasible(&state.games, bag)
+                    .map(|game| game.idx)
+                    .collect::<Vec<_>>();
+                Ok(format!(
+                    "feasible games for {bag}: {} ({} of {})",
+                    list(&mut ids.iter().copied()),
+                    ids.len(),
+                    state.games.len()
+                ))
+            }
+            Query::MinGames(min_games) => {
+                let (bag, count) = smallest_bag(&state.games, &palette, *min_games)
+                    .map_err(|err| err.to_string())?
+                    .ok_or_else(|| format!("there are fewer than {min_games} games"))?;
+                Ok(format!(
+                    "smallest bag for at least {min_games} games: {bag} (total {}, {count} games)",
+                    bag.total().map_err(|err| err.to_string())?,
+                ))
+            }
+            Query::Games(ids) => {
+                let bag = smallest_bag_for(&state.games, &palette, ids)?;
+                Ok(format!(
+                    "smallest bag for games {}: {bag} (total {})",
+                    list(&mut ids.iter().copied()),
+                    bag.total().map_err(|err| err.to_string())?
+                ))
+            }
+            Query::Frontier => {
+                let mut lines = vec!["total  games  bag".to_string()];
+                for (bag, count) in
+                    pareto_frontier(&state.games, &palette).map_err(|err| err.to_string())?
+                {
+                    let total = bag.total().map_err(|err| err.to_string())?;
+                    lines.push(format!("{total:>5}  {count:>5}  {bag}"));
+                }
+                Ok(lines.join("\n"))
+            }
+        }
+    }
+}
+
+/// How many cubes are drawn for each set.
+#[derive(Debug, Clone, PartialEq)]
+enum Handful {
+    /// `uniform:LOW-HIGH`: every size in the range is equally likely.
+    Uniform(std::ops::RangeInclusive<usize>),
+    /// `binomial:P`: each cube in the bag is drawn with probability `P`.
+    Binomial(f64),
+}
+
+impl Handful {
+    /// Draws a handful size. Sizes are clamped to `1..=total`, since a set
+    /// needs at least one cube.
+    fn size(&self, rng: &mut Rng, total: usize) -> usize {
+        let size = match self {
+            Handful::Uniform(range) => {
+                rng.range(*range.start() as u64..=*range.end() as u64) as usize
+            }
+            Handful::Binomial(p) => (0..total).filter(|_| rng.unit() < *p).count(),
+        };
+
+        size.clamp(1, total)
+    }
+}
+
+/// Parses `LOW-HIGH`, or a single number for a fixed value.
+fn parse_range(s: &str) -> Result<std::ops::RangeInclusive<usize>, String> {
+    let invalid = || format!("invalid range `{s}`");
+    let (low, high) = s.split_once('-').unwrap_or((s, s));
+    let low = low.trim().parse::<usize>().map_err(|_| invalid())?;
+    let high = high.trim().parse::<usize>().map_err(|_| invalid())?;
+    if low > high {
+        return Err(invalid());
+    }
+
+    Ok(low..=high)
+}
+
+impl std::str::FromStr for Handful {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once(':') {
+            Some(("uniform", range)) => Ok(Self::Uniform(parse_range(range)?)),
+            Some(("binomial", p)) => match p.parse::<f64>() {
+                Ok(p) if (0.0..=1.0).contains(&p) => Ok(Self::Binomial(p)),
+                _ => Err(format!("invalid probability `{p}`")),
+            },
+            _ => Err(format!("invalid handful `{s}`")),
+        }
+    }
+}
+
+/// Plays games with a known bag: each set is a handful drawn without
+/// replacement, and the cubes go back into the bag before the next set.
+#[derive(Debug, Clone)]
+struct Simulator {
+    bag: Bag,
+    sets: std::ops::RangeInclusive<usize>,
+    handful: Handful,
+}
+
+impl Simulator {
+    fn new(bag: Bag) -> Self {
+        Self {
+            bag,
+            sets: 1..=6,
+            handful: Handful::Uniform(1..=10),
+        }
+    }
+
+    /// Reads `--sets LOW-HIGH` and `--handful uniform:LOW-HIGH|binomial:P`.
+    fn from_args(args: &mut aoc::Args, bag: Bag) -> Result<Self, String> {
+        let mut simulator = Self::new(bag);
+        if simulator.bag.total().map_err(|err| err.to_string())? <= 0 {
+            return Err("cannot draw from an empty bag".to_string());
+        }
+
+        if let Some(sets) = args.value("sets")? {
+            simulator.sets = parse_range(&sets)?;
+            if *simulator.sets.start() == 0 {
+                return Err("games need at least one set".to_string());
+            }
+        }
+        if let Some(handful) = args.value("handful")? {
+            simulator.handful = handful.parse()?;
+        }
+
+        Ok(simulator)
+    }
+
+    fn game(&self, rng: &mut Rng, idx: i32) -> Game {
+        let mut cubes = self
+            .bag
+            .counts
+            .iter()
+            .flat_map(|(color, count)| std::iter::repeat_n(color, (*count).max(0) as usize))
+            .collect::<Vec<_>>();
+
+        let len = rng.range(*self.sets.start() as u64..=*self.sets.end() as u64);
+        let sets = (0..len)
+            .map(|_| {
+                let size = self.handful.size(rng, cubes.len());
+
+                let mut counts = BTreeMap::<String, i32>::new();
+                for color in rng.sample(&mut cubes, size) {
+                    *counts.entry(color.to_string()).or_default() += 1;
+                }
+
+                Set { counts }
+            })
+            .collect();
+
+        Game { idx, sets }
+    }
+
+    /// Plays `len` games numbered from 1.
+    fn games(&self, rng: &mut Rng, len: usize) -> Vec<Game> {
+        (1..=len as i32).map(|idx| self.game(rng, idx)).collect()
+    }
+}
+
+/// Number of buckets in the power histogram of an [`Inspection`].
+const POWER_BUCKETS: i64 = 10;
+
+#[derive(Debug, Clone, PartialEq)]
+struct ColorStats {
+    color: String,
+    min: i32,
+    mean: f64,
+    max: i32,
+    /// How many sets hold each count, a missing color counting as zero.
+    distribution: BTreeMap<i32, usize>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct GameStats {
+    idx: i32,
+    /// The largest count of each palette color in any set.
+    max: Vec<i32>,
+    /// `None` if the power overflows.
+    power: Option<i64>,
+    /// How far each color goes over the bag, for colors that do.
+    excess: Vec<(String, i32)>,
+}
+
+/// Summary statistics of a list of games, checked against a bag.
+#[derive(Debug, Clone, PartialEq)]
+struct Inspection {
+    palette: Vec<String>,
+    colors: Vec<ColorStats>,
+    games: Vec<GameStats>,
+    /// Games per power range, `(low, high, games)` with `high` exclusive.
+    powers: Vec<(i64, i64, usize)>,
+    /// Pearson correlation of the per-set counts of each pair of colors.
+    correlations: Vec<(String, String, f64)>,
+}
+
+impl Inspection {
+    fn new(state: &State, bag: &Bag) -> Self {
+        let palette = state.palette_with(bag);
+        let sets = state
+            .games
+            .iter()
+            .flat_map(|game| &game.sets)
+            .map(|set| {
+                palette
+                    .iter()
+                    .map(|color| set.counts.get(color).copied().unwrap_or_default())
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        let column = |color: usize| sets.iter().map(move |set| set[color]);
+
+        let colors = palette
+            .iter()
+            .enumerate()
+            .map(|(idx, color)| {
+                let mut distribution = BTreeMap::new();
+                for count in column(idx) {
+                    *distribution.entry(count).or_default() += 1;
+                }
+
+                ColorStats {
+                    color: color.clone(),
+                    min: column(idx).min().unwrap_or_default(),
+                    mean: mean(column(idx)),
+                    max: column(idx).max().unwrap_or_default(),
+                    distribution,
+                }
+            })
+            .collect();
+
+        let games = state
+            .games
+            .iter()
+            .map(|game| {
+                let minimum = game.minimum_bag();
+                let max = palette
+                    .iter()
+                    .map(|color| minimum.count(color))
+                    .collect::<Vec<_>>();
+
+                GameStats {
+                    idx: game.idx,
+                    power: palette
+                        .iter()
+                        .zip(&max)
+                        .filter(|(color, _)| state.palette.contains(*color))
+                        .try_fold(1i64, |power, (_, count)| {
+                            power.checked_mul(i64::from(*count))
+                        }),
+                    excess: palette
+                        .iter()
+                        .zip(&max)
+                        .filter(|(color, max)| **max > bag.count(color))
+                        .map(|(color, max)| (color.clone(), max - bag.count(color)))
+                        .collect(),
+                    max,
+                }
+            })
+            .collect::<Vec<_>>();
+
+        let highest = games
+            .iter()
+            .filter_map(|game| game.power)
+            .max()
+            .unwrap_or_default();
+        let width = highest / POWER_BUCKETS + 1;
+        let powers = (0..POWER_BUCKETS)
+            .map(|bucket| {
+                let (low, high) = (bucket * width, (bucket + 1) * width);
+                let games = games
+                    .iter()
+                    .filter(|game| game.power.is_some_and(|power| (low..high).contains(&power)))
+                    .count();
+                (low, high, games)
+            })
+            .collect();
+
+        let correlations = (0..palette.len())
+            .flat_map(|a| (a + 1..palette.len()).map(move |b| (a, b)))
+            .map(|(a, b)| {
+                let (mean_a, mean_b) = (mean(column(a)), mean(column(b)));
+                let (mut covariance, mut var_a, mut var_b) = (0.0, 0.0, 0.0);
+                for (x, y) in column(a).zip(column(b)) {
+                    let (dx, dy) = (f64::from(x) - mean_a, f64::from(y) - mean_b);
+                    covariance += dx * dy;
+                    var_a += dx * dx;
+                    var_b += dy * dy;
+                }
+
+                (
+                    palette[a].clone(),
+                    palette[b].clone(),
+                    covariance / (var_a * var_b).sqrt(),
+                )
+            })
+            .collect();
+
+        Self {
+            palette,
+            colors,
+            games,
+            powers,
+            correlations,
+        }
+    }
+
+    fn text(&self) -> String {
+        let row = |cells: &[&str]| {
+            cells
+                .iter()
+                .map(|cell| cell.to_string())
+                .collect::<Vec<_>>()
+        };
+
+        let mut colors = vec![row(&["color", "min", "mean", "max", "distribution"])];
+        colors.extend(self.colors.iter().map(|stats| {
+            let distribution = stats
+                .distribution
+                .iter()
+                .map(|(count, sets)| format!("{count}:{sets}"))
+                .collect::<Vec<_>>()
+                .join(" ");
+            vec![
+                stats.color.clone(),
+                stats.min.to_string(),
+                format!("{:.2}", stats.mean),
+                stats.max.to_string(),
+                distribution,
+            ]
+        }));
+
+        let mut games = vec![std::iter::once("game".to_string())
+            .chain(self.palette.iter().cloned())
+            .chain(["power".to_string(), "over bag".to_string()])
+            .collect::<Vec<_>>()];
+        games.extend(self.games.iter().map(|game| {
+            let power = game
+                .power
+                .map_or_else(|| "overflow".to_string(), |power| power.to_string());
+            let excess = game
+                .excess
+                .iter()
+                .map(|(color, excess)| format!("{color} +{excess}"))
+                .collect::<Vec<_>>()
+                .join(", ");
+
+            std::iter::once(game.idx.to_string())
+                .chain(game.max.iter().map(|count| count.to_string()))
+                .chain([power, excess])
+                .collect()
+        }));
+
+        let mut powers = vec![row(&["power", "games"])];
+        powers.extend(self.powers.iter().map(|(low, high, games)| {
+            vec![
+                format!("{low}..{high}"),
+                games.to_string(),
+                "#".repeat(*games),
+            ]
+        }));
+
+        let mut correlations = vec![row(&["colors", "correlation"])];
+        correlations.extend(self.correlations.iter().map(|(a, b, r)| {
+            vec![
+                format!("{a} ~ {b}"),
+                if r.is_finite() {
+                    format!("{r:.3}")
+                } else {
+                    "n/a".to_string()
+                },
+            ]
+        }));
+
+        [colors, games, powers, correlations]
+            .map(|rows| aoc::table::render(&rows))
+            .join("\n\n")
+    }
+
+    fn json(&self) -> aoc::json::Value {
+        use aoc::json::Value;
+
+        let object = |members: Vec<(&str, Value)>| {
+            Value::Object(
+                members
+                    .into_iter()
+                    .map(|(name, value)| (name.to_string(), value))
+                    .collect(),
+            )
+        };
+        let int = |n: i32| Value::from(i64::from(n));
+
+        object(vec![
+            (
+                "colors",
+                Value::Array(
+                    self.colors
+                        .iter()
+                        .map(|stats| {
+                            object(vec![
+                                ("color", stats.color.as_str().into()),
+                                ("min", int(stats.min)),
+                                ("mean", stats.mean.into()),
+                                ("max", int(stats.max)),
+                                (
+                                    "distribution",
+                                    Value::Object(
+                                        stats
+                                            .distribution
+                                            .iter()
+                                            .map(|(count, sets)| {
+                                                (count.to_string(), Value::from(*sets as i64))
+                                            })
+                                            .collect(),
+                                    ),
+                                ),
+                            ])
+                        })
+                        .collect(),
+                ),
+            ),
+            (
+                "games",
+                Value::Array(
+                    self.games
+                        .iter()
+                        .map(|game| {
+                            let counts = |counts: &mut dyn Iterator<Item = (&String, &i32)>| {
+                                Value::Object(
+                                    counts
+                                        .map(|(color, count)| (color.clone(), int(*count)))
+                                        .collect(),
+                                )
+                            };
+
+                            object(vec![
+                                ("id", int(game.idx)),
+                                ("max", counts(&mut self.palette.iter().zip(&game.max))),
+                                ("power", game.power.map_or(Value::Null, Value::from)),
+                                (
+                                    "over_bag",
+                                    counts(
+                                        &mut game
+                                            .excess
+                                            .iter()
+                                            .map(|(color, excess)| (color, excess)),
+                                    ),
+                                ),
+                            ])
+                        })
+                        .collect(),
+                ),
+            ),
+            (
+                "powers",
+                Value::Array(
+                    self.powers
+                        .iter()
+                        .map(|(low, high, games)| {
+                            object(vec![
+                                ("low", (*low).into()),
+                                ("high", (*high).into()),
+                                ("games", (*games as i64).into()),
+                            ])
+                        })
+                        .collect(),
+                ),
+            ),
+            (
+                "correlations",
+                Value::Array(
+                    self.correlations
+                        .iter()
+                        .map(|(a, b, r)| {
+                            object(vec![
+                                ("a", a.as_str().into()),
+                                ("b", b.as_str().into()),
+                                ("r", (*r).into()),
+                            ])
+                        })
+                        .collect(),
+                ),
+            ),
+        ])
+    }
+}
+
+fn mean(values: impl Iterator<Item = i32>) -> f64 {
+    let (sum, len) = values.fold((0.0, 0), |(sum, len), value| {
+        (sum + f64::from(value), len + 1)
+    });
+    if len == 0 {
+        0.0
+    } else {
+        sum / f64::from(len)
+    }
+}
+
+/// Upper bound on the cubes of a single color considered when estimating.
+const MAX_CUBES: i32 = 100;
+
+/// Twice the log-likelihood drop allowed inside a 95% confidence interval,
+/// the 0.95 quantile of the chi-squared distribution with one degree of
+/// freedom.
+const CHI_SQUARED_95: f64 = 3.841;
+
+/// A range of cube counts. `capped` means the range was cut off at
+/// [`MAX_CUBES`] and may reach further.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Interval {
+    low: i32,
+    high: i32,
+    capped: bool,
+}
+
+impl std::fmt::Display for Interval {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}..{}", self.low, self.high)?;
+        if self.capped {
+            f.write_str("+")?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Estimate {
+    bag: Bag,
+    log_likelihood: f64,
+    /// 95% confidence interval of each color, with the other colors held at
+    /// their estimate.
+    intervals: Vec<(String, Interval)>,
+    /// Likelihood of the reference bag relative to the estimate, between 0
+    /// and 1.
+    reference: f64,
+}
+
+/// Estimates bags by maximum likelihood. Each set is taken to be a handful
+/// drawn without replacement from the bag, and the cubes are put back before
+/// the next set, so a set has multivariate hypergeometric probability
+/// `prod(C(n_c, x_c)) / C(N, k)`.
+struct Estimator {
+    palette: Vec<String>,
+    ln_factorials: Vec<f64>,
+}
+
+impl Estimator {
+    fn new(palette: Vec<String>) -> Self {
+        let len = palette.len().max(1) * MAX_CUBES as usize + 1;
+        let mut ln_factorials = vec![0.0; len];
+        for n in 1..len {
+            ln_factorials[n] = ln_factorials[n - 1] + (n as f64).ln();
+        }
+
+        Self {
+            palette,
+            ln_factorials,
+        }
+    }
+
+    fn ln_factorial(&self, n: i32) -> f64 {
+        match self.ln_factorials.get(n as usize) {
+            Some(ln) => *ln,
+            None => (1..=n).map(|n| (n as f64).ln()).sum(),
+        }
+    }
+
+    fn ln_choose(&self, n: i32, k: i32) -> f64 {
+        if k < 0 || k > n {
+            return f64::NEG_INFINITY;
+        }
+
+        self.ln_factorial(n) - self.ln_factorial(k) - self.ln_factorial(n - k)
+    }
+
+    fn log_likelihood(&self, bag: &[i32], sets: &[Vec<i32>]) -> Result<f64, Overflow> {
+        let total = checked::sum(DAY, bag.iter().copied())?;
+
+        sets.iter()
+            .map(|set| {
+                let ways = bag
+                    .iter()
+                    .zip(set)
+                    .map(|(&n, &x)| self.ln_choose(n, x))
+                    .sum::<f64>();
+                if ways == f64::NEG_INFINITY {
+                    // The set does not fit in the bag.
+                    return Ok(ways);
+                }
+
+                Ok(ways - self.ln_choose(total, checked::sum(DAY, set.iter().copied())?))
+            })
+            .sum()
+    }
+
+    /// Likelihoods of every count of color `color`, the other colors taken
+    /// from `bag`.
+    fn profile(
+        &self,
+        bag: &[i32],
+        color: usize,
+        range: std::ops::RangeInclusive<i32>,
+        sets: &[Vec<i32>],
+    ) -> Result<Vec<(i32, f64)>, Overflow> {
+        range
+            .map(|count| {
+                let mut bag = bag.to_vec();
+                bag[color] = count;
+                Ok((count, self.log_likelihood(&bag, sets)?))
+            })
+            .collect()
+    }
+
+    fn estimate<'a>(
+        &self,
+        sets: impl IntoIterator<Item = &'a Set>,
+        reference: &Bag,
+    ) -> Result<Estimate, Overflow> {
+        let sets = sets
+            .into_iter()
+            .map(|set| {
+                self.palette
+                    .iter()
+                    .map(|color| set.counts.get(color).copied().unwrap_or_default())
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+
+        let minimum = (0..self.palette.len())
+            .map(|color| sets.iter().map(|set| set[color]).max().unwrap_or_default())
+            .collect::<Vec<_>>();
+        let range = |color: usize| minimum[color]..=MAX_CUBES.max(minimum[color]);
+
+        // Coordinate ascent from the minimum bag, preferring fewer cubes on
+        // ties so the search always settles.
+        let mut bag = minimum.clone();
+        let mut log_likelihood = self.log_likelihood(&bag, &sets)?;
+        loop {
+            let mut changed = false;
+            for color in 0..self.palette.len() {
+                let (count, best) = self
+                    .profile(&bag, color, range(color), &sets)?
+                    .into_iter()
+                    .fold((bag[color], log_likelihood), |best, (count, ll)| {
+                        if ll > best.1 + 1e-12 {
+                            (count, ll)
+                        } else {
+                            best
+                        }
+                    });
+                if count != bag[color] {
+                    bag[color] = count;
+                    log_likelihood = best;
+                    changed = true;
+                }
+            }
+
+            if !changed {
+                break;
+            }
+        }
+
+        let intervals = (0..self.palette.len())
+            .map(|color| {
+                let inside = self
+                    .profile(&bag, color, range(color), &sets)?
+                    .into_iter()
+                    .filter(|(_, ll)| 2.0 * (log_likelihood - ll) <= CHI_SQUARED_95)
+                    .map(|(count, _)| count)
+                    .collect::<Vec<_>>();
+                let low = inside.first().copied().unwrap_or(bag[color]);
+                let high = inside.last().copied().unwrap_or(bag[color]);
+
+                Ok((
+                    self.palette[color].clone(),
+                    Interval {
+                        low,
+                        high,
+                        capped: high == *range(color).end(),
+                    },
+                ))
+            })
+            .collect::<Result<_, Overflow>>()?;
+
+        let reference = self
+            .palette
+            .iter()
+            .map(|color| reference.count(color))
+            .collect::<Vec<_>>();
+        let reference = (self.log_likelihood(&reference, &sets)? - log_likelihood).exp();
+
+        Ok(Estimate {
+            bag: Bag {
+                counts: self.palette.iter().cloned().zip(bag).collect(),
+            },
+            log_likelihood,
+            intervals,
+            reference,
         })
-        .sum()
+    }
+}
+
+impl std::fmt::Display for Estimate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "bag {}, log-likelihood {:.3}, 95% intervals ",
+            self.bag, self.log_likelihood
+        )?;
+        for (idx, (color, interval)) in self.intervals.iter().enumerate() {
+            if idx > 0 {
+                f.write_str(", ")?;
+            }
+            write!(f, "{color} {interval}")?;
+        }
+        write!(f, ", reference likelihood {:.4}", self.reference)
+    }
 }
 
-fn main() {
-    let state = aoc::get_input();
+/// Estimates the bag of each game and of all games together, comparing each
+/// estimate with `reference`.
+fn estimate_report(state: &State, reference: &Bag) -> Result<String, Error> {
+    let estimator = Estimator::new(state.palette_with(reference));
+
+    let mut lines = Vec::with_capacity(state.games.len() + 1);
+    for game in &state.games {
+        aoc::checkpoint()?;
+        let estimate = estimator.estimate(&game.sets, reference)?;
+        lines.push(format!("game {}: {estimate}", game.idx));
+    }
+    let estimate = estimator.estimate(state.games.iter().flat_map(|game| &game.sets), reference)?;
+    lines.push(format!("all games: {estimate}"));
+
+    Ok(lines.join("\n"))
+}
 
-    println!("part 1: {}", part_1(&state));
+fn part_1(state: &State, bag: &Bag) -> Result<i32, Error> {
+    let sum = checked::sum(DAY, feasible(&state.games, bag).map(|game| game.idx))?;
 
-    println!("part 2: {}", part_2(&state));
+    Ok(sum)
+}
+
+/// The power of a game multiplies its minimum counts of every color seen in
+/// the input. Colors that only the bag has are left out, as no game could
+/// need them.
+fn part_2(state: &State) -> Result<i32, Error> {
+    checked::try_sum(
+        DAY,
+        state
+            .games
+            .iter()
+            .filter(|game| !game.sets.is_empty())
+            .map(|game| {
+                let minimum = game.minimum_bag();
+
+                aoc::debug!("game {}: minimum bag {minimum}", game.idx);
+
+                checked::product(DAY, state.palette.iter().map(|color| minimum.count(color)))
+                    .map_err(Error::from)
+            }),
+    )
+}
+
+fn main() -> std::process::ExitCode {
+    let mut args = aoc::Args::from_env();
+    let bag = match Bag::from_args(&mut args) {
+        Ok(bag) => bag,
+        Err(err) => return aoc::runner::usage_error(DAY, err),
+    };
+
+    let from = args
+        .value("from")
+        .and_then(|from| from.map(|from| from.parse()).transpose());
+    let to = args
+        .value("to")
+        .and_then(|to| to.map(|to| to.parse()).transpose());
+    let (from, to) = match (from, to) {
+        (Ok(from), Ok(to)) => (from, to),
+        (Err(err), _) | (_, Err(err)) => return aoc::runner::usage_error(DAY, err),
+    };
+
+    let simulate = args.value("simulate").and_then(|games| {
+        games
+            .map(|games| {
+                let games = games
+                    .parse::<usize>()
+                    .map_err(|_| format!("invalid game count `{games}`"))?;
+                let seed = args.value("seed")?.map_or_else(
+                    || Ok(Rng::seed_from_time()),
+                    |seed| seed.parse().map_err(|_| format!("invalid seed `{seed}`")),
+                )?;
+                let simulator = Simulator::from_args(&mut args, bag.clone())?;
+                Ok((simulator, games, seed))
+            })
+            .transpose()
+    });
+    match simulate {
+        Err(err) => return aoc::runner::usage_error(DAY, err),
+        Ok(Some((simulator, games, seed))) => {
+            if let Err(err) = args.finish() {
+                return aoc::runner::usage_error(DAY, err);
+            }
+
+            eprintln!(
+                "day {DAY}: simulated bag {} with seed {seed}",
+                simulator.bag
+            );
+
+            let games = simulator.games(&mut Rng::new(seed), games);
+            print!("{}", to.unwrap_or(Format::Text).write(&games));
+
+            return std::process::ExitCode::SUCCESS;
+        }
+        Ok(None) => {}
+    }
+
+    match (from, to) {
+        (None, None) => {}
+        (from, to) => {
+            return aoc::runner::run_task(DAY, args, move || {
+                let input = std::io::read_to_string(std::io::stdin())
+                    .map_err(|err| format!("cannot read stdin: {err}"))?;
+                let games = from.unwrap_or(Format::Text).read(&input)?;
+                Ok::<_, String>(
+                    to.unwrap_or(Format::Text)
+                        .write(&games)
+                        .trim_end_matches('\n')
+                        .to_string(),
+                )
+            });
+        }
+    }
+
+    let queries = match Query::from_args(&mut args) {
+        Ok(queries) => queries,
+        Err(err) => return aoc::runner::usage_error(DAY, err),
+    };
+    if !queries.is_empty() {
+        let bag = bag.clone();
+        return aoc::runner::run_mode(DAY, args, State::parse, move |state: &State| {
+            queries
+                .iter()
+                .map(|query| query.answer(state, &bag))
+                .collect::<Result<Vec<_>, _>>()
+                .map(|answers| answers.join("\n"))
+        });
+    }
+
+    if args.flag("inspect") {
+        let json = args.flag("json");
+        let bag = bag.clone();
+        return aoc::runner::run_mode(DAY, args, State::parse, move |state: &State| {
+            let inspection = Inspection::new(state, &bag);
+            Ok::<_, Error>(if json {
+                inspection.json().to_string()
+            } else {
+                inspection.text()
+            })
+        });
+    }
+
+    if args.flag("estimate") {
+        let bag = bag.clone();
+        return aoc::runner::run_mode(DAY, args, State::parse, move |state: &State| {
+            estimate_report(state, &bag)
+        });
+    }
+
+    aoc::runner::run_parsed(
+        DAY,
+        args,
+        State::parse,
+        move |state: &State| part_1(state, &bag),
+        part_2,
+    )
 }
 
 #[cfg(test)]
@@ -125,27 +1544,373 @@ mod tests {
     fn test_example_part_1() {
         let state = aoc::get_input_from(EXAMPLE_INPUT);
 
-        assert_eq!(part_1(&state), 8);
+        assert_eq!(part_1(&state, &Bag::default()), Ok(8));
     }
 
     #[test]
     fn test_example_part_2() {
         let state = aoc::get_input_from(EXAMPLE_INPUT);
 
-        assert_eq!(part_2(&state), 2286);
+        assert_eq!(part_2(&state), Ok(2286));
+    }
+
+    #[test]
+    fn test_palette() {
+        let state = aoc::get_input_from(
+            "Game 1: 3 yellow, 4 red; 2 green, 1 blue\nGame 2: 6 yellow, 1 red, 1 green, 1 blue",
+        );
+        let bag = "red=12,green=13,blue=14,yellow=5".parse::<Bag>().unwrap();
+
+        assert_eq!(part_1(&state, &bag), Ok(1));
+        assert_eq!(part_1(&state, &Bag::default()), Ok(0));
+        assert_eq!(part_2(&state), Ok(3 * 4 * 2 + 6));
+
+        let state = aoc::get_input_from(EXAMPLE_INPUT);
+        let purple = "red=12,green=13,blue=14,purple=5".parse::<Bag>().unwrap();
+        assert_eq!(part_1(&state, &purple), part_1(&state, &Bag::default()));
+        assert_eq!(part_2(&state), Ok(2286));
+
+        assert_eq!(bag.to_string(), "blue=14,green=13,red=12,yellow=5");
+        assert!("red=-1".parse::<Bag>().is_err());
+        assert!("red".parse::<Bag>().is_err());
+    }
+
+    #[test]
+    fn test_estimate() {
+        let state = aoc::get_input_from::<State>("Game 1: 1 red, 1 blue");
+        let estimator = Estimator::new(state.palette_with(&Bag::default()));
+        let estimate = estimator
+            .estimate(&state.games[0].sets, &Bag::default())
+            .unwrap();
+
+        assert_eq!(estimate.bag.to_string(), "blue=1,green=0,red=1");
+        assert!(estimate.log_likelihood.abs() < 1e-9);
+        assert_eq!(
+            estimate.intervals[2],
+            (
+                "red".to_string(),
+                Interval {
+                    low: 1,
+                    high: 12,
+                    capped: false
+                }
+            )
+        );
+        // 12 * 14 ways to draw one red and one blue out of C(39, 2).
+        assert!((estimate.reference - 168.0 / 741.0).abs() < 1e-9);
+
+        let state = aoc::get_input_from::<State>(EXAMPLE_INPUT);
+        let estimator = Estimator::new(state.palette_with(&Bag::default()));
+        let estimate = estimator
+            .estimate(
+                state.games.iter().flat_map(|game| &game.sets),
+                &Bag::default(),
+            )
+            .unwrap();
+        assert!(estimate.bag.count("red") >= 20);
+        assert_eq!(estimate.reference, 0.0);
+
+        let state = aoc::get_input_from::<State>("Game 1: 20 red, 1 blue");
+        let estimator = Estimator::new(state.palette_with(&Bag::default()));
+        let estimate = estimator
+            .estimate(&state.games[0].sets, &Bag::default())
+            .unwrap();
+        assert_eq!(estimate.reference, 0.0);
+
+        #[cfg(feature = "checked")]
+        assert_eq!(
+            estimator.log_likelihood(&[i32::MAX, 1], &[]),
+            Err(Overflow {
+                day: DAY,
+                op: checked::Op::Add,
+                lhs: i32::MAX.into(),
+                rhs: 1,
+            })
+        );
+    }
+
+    #[test]
+    fn test_queries() {
+        let state = aoc::get_input_from::<State>(EXAMPLE_INPUT);
+        let palette = state.palette_with(&Bag::default());
+
+        let ids = feasible(&state.games, &Bag::default())
+            .map(|game| game.idx)
+            .collect::<Vec<_>>();
+        assert_eq!(ids, [1, 2, 5]);
+
+        let (bag, count) = smallest_bag(&state.games, &palette, 4).unwrap().unwrap();
+        assert_eq!(
+            (bag.to_string(), count),
+            ("blue=15,green=3,red=14".to_string(), 4)
+        );
+        assert_eq!(smallest_bag(&state.games, &palette, 6), Ok(None));
+
+        let bag = smallest_bag_for(&state.games, &palette, &BTreeSet::from([1, 3])).unwrap();
+        assert_eq!(bag.to_string(), "blue=6,green=13,red=20");
+        assert!(smallest_bag_for(&state.games, &palette, &BTreeSet::from([9])).is_err());
+
+        let frontier = pareto_frontier(&state.games, &palette)
+            .unwrap()
+            .into_iter()
+            .map(|(bag, count)| (bag.total().unwrap(), count))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            frontier,
+            [(0, 0), (8, 1), (13, 2), (15, 3), (32, 4), (48, 5)]
+        );
+    }
+
+    #[test]
+    fn test_search() {
+        for seed in 0..10 {
+            let games = random_games(seed, 6);
+            let palette = games
+                .iter()
+                .flat_map(|game| game.minimum_bag().counts.into_keys())
+                .collect::<BTreeSet<_>>()
+                .into_iter()
+                .collect::<Vec<_>>();
+
+            // Every bag a game's minimum can make, with its size and count.
+            let mut bags = vec![BTreeMap::new()];
+            for color in &palette {
+                let values = std::iter::once(0)
+                    .chain(games.iter().map(|game| game.minimum_bag().count(color)))
+                    .collect::<BTreeSet<_>>();
+                bags = bags
+                    .into_iter()
+                    .flat_map(|counts| {
+                        values.iter().map(move |value| {
+                            let mut counts = counts.clone();
+                            counts.insert(color.clone(), *value);
+                            counts
+                        })
+                    })
+                    .collect();
+            }
+            let sized = bags
+                .into_iter()
+                .map(|counts| {
+                    let bag = Bag { counts };
+                    let count = feasible(&games, &bag).count();
+                    (bag.total().unwrap(), count)
+                })
+                .collect::<Vec<_>>();
+
+            for min_games in 0..=games.len() + 1 {
+                let expected = sized
+                    .iter()
+                    .filter(|(_, count)| *count >= min_games)
+                    .min_by_key(|(total, count)| (*total, std::cmp::Reverse(*count)))
+                    .copied();
+                let found =
+                    smallest_bag(&games, &palette, min_games)
+                        .unwrap()
+                        .map(|(bag, count)| {
+                            assert_eq!(feasible(&games, &bag).count(), count);
+                            (bag.total().unwrap(), count)
+                        });
+                assert_eq!(found, expected, "seed {seed}, {min_games} games");
+            }
+        }
+    }
+
+    /// Games with a few sets of random colors and counts, seeded so failures
+    /// reproduce.
+    fn random_games(seed: u64, len: usize) -> Vec<Game> {
+        const COLORS: &[&str] = &["red", "green", "blue", "grün", "x-ray", "ultra:violet"];
+
+        let mut rng = Rng::new(seed);
+        let mut next = move |bound: u64| rng.below(bound);
+
+        let mut idx = 0;
+        (0..len)
+            .map(|_| Game {
+                idx: {
+                    idx += 1 + next(10) as i32;
+                    idx
+                },
+                sets: (0..1 + next(5))
+                    .map(|_| Set {
+                        counts: (0..1 + next(4))
+                            .map(|_| {
+                                (
+                                    COLORS[next(COLORS.len() as u64) as usize].to_string(),
+                                    next(30) as i32,
+                                )
+                            })
+                            .collect(),
+                    })
+                    .collect(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let messy =
+            "Game 1:3 blue,4 red;  1 red , 2 green, 6 blue ;2 green\n\nGame  2: 1 red, 2 red";
+        let games = Format::Text.read(messy).unwrap();
+        assert_eq!(
+            Format::Text.write(&games),
+            "Game 1: 3 blue, 4 red; 6 blue, 2 green, 1 red; 2 green\nGame 2: 3 red\n"
+        );
+
+        let input = Format::Text.read(INPUT).unwrap();
+        for seed in 0..100 {
+            let games = if seed == 0 {
+                input.clone()
+            } else {
+                random_games(seed, 20)
+            };
+
+            for format in [Format::Text, Format::Json, Format::Csv] {
+                let written = format.write(&games);
+                let read = format.read(&written).unwrap();
+                assert_eq!(read, games, "{format:?} with seed {seed}");
+                assert_eq!(format.write(&read), written);
+            }
+        }
+
+        let repeated = [
+            (Format::Text, "Game 1: 1 red, 2 red, 3 blue"),
+            (
+                Format::Json,
+                r#"[{"id":1,"sets":[{"red":1,"red":2,"blue":3}]}]"#,
+            ),
+            (
+                Format::Csv,
+                "game,set,color,count\n1,1,red,1\n1,1,red,2\n1,1,blue,3",
+            ),
+        ];
+        for (format, input) in repeated {
+            let games = format.read(input).unwrap();
+            assert_eq!(
+                Format::Text.write(&games),
+                "Game 1: 3 blue, 3 red\n",
+                "{format:?}"
+            );
+        }
+
+        assert!(Format::Csv.read("game,set,color,count\n1,2,red,3").is_err());
+        assert!(Format::Json
+            .read(r#"[{"id":1,"sets":[{"dark red":1}]}]"#)
+            .is_err());
+    }
+
+    #[test]
+    fn test_simulator() {
+        let bag = "red=12,green=13,blue=14,yellow=5".parse::<Bag>().unwrap();
+        let mut simulator = Simulator::new(bag.clone());
+
+        let games = simulator.games(&mut Rng::new(1), 50);
+        assert_eq!(
+            Format::Text.write(&games),
+            Format::Text.write(&simulator.games(&mut Rng::new(1), 50))
+        );
+        assert!(games.iter().all(|game| (1..=6).contains(&game.sets.len())));
+
+        let state = Format::Text.write(&games).lines().collect::<State>();
+        assert_eq!(part_1(&state, &bag), Ok((1..=50).sum()));
+
+        simulator.sets = 3..=3;
+        simulator.handful = Handful::Binomial(1.0);
+        for game in simulator.games(&mut Rng::new(2), 5) {
+            assert_eq!(game.sets.len(), 3);
+            assert!(game.sets.iter().all(|set| bag.holds(set)
+                && *set
+                    == Set {
+                        counts: bag.counts.clone()
+                    }));
+        }
+
+        assert_eq!("uniform:2-4".parse(), Ok(Handful::Uniform(2..=4)));
+        assert!("binomial:1.5".parse::<Handful>().is_err());
+    }
+
+    #[test]
+    fn test_inspect() {
+        let state = aoc::get_input_from::<State>(EXAMPLE_INPUT);
+        let inspection = Inspection::new(&state, &Bag::default());
+
+        assert_eq!(inspection.palette, ["blue", "green", "red"]);
+        let red = &inspection.colors[2];
+        assert_eq!((red.min, red.max), (0, 20));
+        assert_eq!(red.distribution.values().sum::<usize>(), 14);
+
+        let failing = inspection
+            .games
+            .iter()
+            .filter(|game| !game.excess.is_empty())
+            .map(|game| (game.idx, game.excess.clone()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            failing,
+            [
+                (3, vec![("red".to_string(), 8)]),
+                (4, vec![("blue".to_string(), 1), ("red".to_string(), 2)])
+            ]
+        );
+        assert_eq!(
+            inspection
+                .games
+                .iter()
+                .filter_map(|game| game.power)
+                .sum::<i64>(),
+            2286
+        );
+        assert_eq!(
+            inspection
+                .powers
+                .iter()
+                .map(|(_, _, games)| games)
+                .sum::<usize>(),
+            5
+        );
+        assert_eq!(inspection.correlations.len(), 3);
+
+        let json = inspection.json();
+        assert_eq!(aoc::json::Value::parse(&json.to_string()), Ok(json));
+        assert_eq!(
+            inspection.games[3],
+            GameStats {
+                idx: 4,
+                max: vec![15, 3, 14],
+                power: Some(630),
+                excess: vec![("blue".to_string(), 1), ("red".to_string(), 2)],
+            }
+        );
+
+        let text = inspection.text();
+        let cells = |start: &str| {
+            text.lines()
+                .find(|line| line.starts_with(start))
+                .map(|line| line.split_whitespace().collect::<Vec<_>>())
+        };
+        assert_eq!(
+            cells("game "),
+            Some(vec!["game", "blue", "green", "red", "power", "over", "bag"])
+        );
+        assert_eq!(
+            cells("4 "),
+            Some(vec![
+                "4", "15", "3", "14", "630", "blue", "+1,", "red", "+2"
+            ])
+        );
     }
 
     #[test]
     fn test_part_1() {
         let state = aoc::get_input_from(INPUT);
 
-        assert_eq!(part_1(&state), 2486);
+        assert_eq!(part_1(&state, &Bag::default()), Ok(2486));
     }
 
     #[test]
     fn test_part_2() {
         let state = aoc::get_input_from(INPUT);
 
-        assert_eq!(part_2(&state), 87984);
+        assert_eq!(part_2(&state), Ok(87984));
     }
 }
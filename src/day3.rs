@@ -1,152 +1,232 @@
-#[derive(Debug)]
+use std::collections::{HashMap, HashSet};
+use std::io::BufRead;
+
+use aoc::grid::Pos;
+use aoc::json::Value;
+use aoc::{checked, Error, Grid, Overflow};
+
+const DAY: u8 = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum Symbol {
     Number(u32),
     Star,
     Unknown(char),
-    Dot,
 }
 
+impl Symbol {
+    /// The char of a symbol, or `None` for a number.
+    fn as_char(&self) -> Option<char> {
+        match self {
+            Symbol::Number(_) => None,
+            Symbol::Star => Some('*'),
+            Symbol::Unknown(c) => Some(*c),
+        }
+    }
+}
+
+/// A number or a symbol, spanning `range` in `row`.
 #[derive(Debug)]
 struct Node {
     symbol: Symbol,
+    row: usize,
     range: std::ops::Range<usize>,
 }
 
 #[derive(Debug)]
-struct Row {
+struct State {
+    grid: Grid<char>,
+    /// Numbers and symbols in reading order.
     nodes: Vec<Node>,
+    /// The index in `nodes` of the node covering each cell.
+    owners: Grid<Option<usize>>,
 }
 
-impl std::str::FromStr for Row {
-    type Err = ();
+impl State {
+    fn parse<S: AsRef<str>>(lines: impl IntoIterator<Item = S>) -> Result<Self, Error> {
+        let grid = Grid::from_lines_padded(lines, '.', |c| c);
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        s.chars()
-            .enumerate()
-            .try_fold(Self { nodes: Vec::new() }, |mut row, (idx, c)| {
-                let symbol = match c {
-                    '*' => Symbol::Star,
-                    '.' => Symbol::Dot,
-                    _ => {
-                        if c.is_numeric() {
-                            Symbol::Number(c.to_digit(10).unwrap())
-                        } else {
-                            Symbol::Unknown(c)
-                        }
-                    }
-                };
+        let nodes = parse_nodes(&grid)?;
 
-                if let Some(node) = row.nodes.last_mut() {
-                    match (&mut node.symbol, &symbol) {
-                        (Symbol::Number(value), Symbol::Number(new_value)) => {
-                            *value = *value * 10 + new_value;
-                            node.range.end = idx + 1;
-                        }
-                        (Symbol::Unknown(a), Symbol::Unknown(b)) if a == b => {
-                            node.range.end = idx + 1;
-                        }
-                        (Symbol::Dot, Symbol::Dot) => {
-                            node.range.end = idx + 1;
-                        }
-                        (Symbol::Star, Symbol::Star) => {
-                            node.range.end = idx + 1;
-                        }
-                        _ => {
-                            row.nodes.push(Node {
-                                symbol,
-                                range: idx..idx + 1,
-                            });
-                        }
-                    }
-                } else {
-                    row.nodes.push(Node {
-                        symbol,
-                        range: idx..idx + 1,
-                    });
-                }
+        let mut owners = grid.map(|_| None);
+        for (idx, node) in nodes.iter().enumerate() {
+            for col in node.range.clone() {
+                owners[Pos::new(node.row, col)] = Some(idx);
+            }
+        }
 
-                Ok(row)
-            })
+        Ok(Self {
+            grid,
+            nodes,
+            owners,
+        })
+    }
+
+    /// The indices of the nodes within `hood` of `node`, in reading order.
+    fn neighbor_indices(&self, node: &Node, hood: &Neighborhood) -> Vec<usize> {
+        let offsets = hood.offsets();
+        let around = if hood.toroidal {
+            self.grid
+                .wrapping_around(node.row, node.range.clone(), &offsets)
+        } else {
+            self.grid.around(node.row, node.range.clone(), &offsets)
+        };
+
+        let mut neighbors = around
+            .into_iter()
+            .filter_map(|pos| self.owners[pos])
+            .collect::<Vec<_>>();
+        neighbors.sort();
+        neighbors.dedup();
+        neighbors
+    }
+
+    /// The nodes within `hood` of `node`, in reading order.
+    fn neighbors(&self, node: &Node, hood: &Neighborhood) -> impl Iterator<Item = &Node> {
+        self.neighbor_indices(node, hood)
+            .into_iter()
+            .map(|idx| &self.nodes[idx])
     }
 }
 
-#[derive(Debug, Default)]
-struct State {
-    rows: Vec<Row>,
+/// The numbers and symbols of `grid`, in reading order.
+fn parse_nodes(grid: &Grid<char>) -> Result<Vec<Node>, Error> {
+    let mut nodes = Vec::new();
+
+    // Digits share a key so a number is one run; any other symbol is a
+    // run of the same char, so `**` is one node.
+    for run in grid.runs(|&c| match c {
+        '.' => None,
+        c if c.is_ascii_digit() => Some('0'),
+        c => Some(c),
+    }) {
+        let symbol = match run.key {
+            '0' => Symbol::Number(run.positions().try_fold(0, |value, pos| {
+                let digit = grid[pos].to_digit(10).unwrap();
+                checked::add(DAY, checked::mul(DAY, value, 10)?, digit)
+            })?),
+            '*' => Symbol::Star,
+            c => Symbol::Unknown(c),
+        };
+
+        nodes.push(Node {
+            symbol,
+            row: run.row,
+            range: run.cols,
+        });
+    }
+
+    Ok(nodes)
 }
 
 impl<D: AsRef<str>> std::iter::FromIterator<D> for State {
     fn from_iter<T: IntoIterator<Item = D>>(iter: T) -> Self {
+        Self::parse(iter).unwrap_or_else(|err| panic!("{err}"))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Shape {
+    /// Every cell within `radius` rows and columns.
+    Moore,
+    /// Every cell within `radius` steps up, down, left or right.
+    VonNeumann,
+}
+
+impl std::str::FromStr for Shape {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "moore" => Ok(Self::Moore),
+            "von-neumann" => Ok(Self::VonNeumann),
+            _ => Err(format!("invalid neighborhood `{s}`")),
+        }
+    }
+}
+
+/// Which cells count as touching a number or symbol. The puzzle uses the
+/// Moore neighborhood of radius 1.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Neighborhood {
+    shape: Shape,
+    radius: usize,
+    /// Whether the edges of the schematic wrap around.
+    toroidal: bool,
+}
+
+impl Default for Neighborhood {
+    fn default() -> Self {
         Self {
-            rows: iter
-                .into_iter()
-                .map(|s| s.as_ref().parse())
-                .collect::<Result<_, _>>()
-                .unwrap(),
+            shape: Shape::Moore,
+            radius: 1,
+            toroidal: false,
         }
     }
 }
 
-fn search_grid<R>(
-    grid: &State,
-    matcher: impl Fn(&Node) -> Option<R> + Clone + Copy,
-    combiner: impl Fn(R, [&[Node]; 3]) -> Option<u32>,
-) -> u32 {
-    grid.rows
-        .iter()
-        .enumerate()
-        .flat_map(|(ridx, row)| {
-            row.nodes
-                .iter()
-                .filter_map(move |node| matcher(node).map(|value| (ridx, &node.range, value)))
-        })
-        .map(|(ridx, range, item)| {
-            let rows = [
-                ridx.checked_sub(1).and_then(|x| grid.rows.get(x)),
-                grid.rows.get(ridx),
-                grid.rows.get(ridx + 1),
-            ];
-
-            let mut iter = rows.into_iter().map(move |row| {
-                let Some(row) = row else {
-                    return &[] as &[Node];
+impl Neighborhood {
+    /// Reads `--neighborhood`, `--radius` and `--toroidal`, defaulting to the
+    /// puzzle's neighborhood.
+    fn from_args(args: &mut aoc::Args) -> Result<Self, String> {
+        let mut hood = Self::default();
+
+        if let Some(shape) = args.value("neighborhood")? {
+            hood.shape = shape.parse()?;
+        }
+        if let Some(radius) = args.value("radius")? {
+            hood.radius = radius
+                .parse()
+                .map_err(|_| format!("invalid radius `{radius}`"))?;
+        }
+        hood.toroidal = args.flag("toroidal");
+
+        Ok(hood)
+    }
+
+    fn offsets(&self) -> Vec<(isize, isize)> {
+        let radius = self.radius as isize;
+        let mut offsets = Vec::new();
+        for rows in -radius..=radius {
+            for cols in -radius..=radius {
+                let inside = match self.shape {
+                    Shape::Moore => true,
+                    Shape::VonNeumann => rows.abs() + cols.abs() <= radius,
                 };
+                if inside && (rows, cols) != (0, 0) {
+                    offsets.push((rows, cols));
+                }
+            }
+        }
+        offsets
+    }
+}
 
-                let start = row
-                    .nodes
-                    .iter()
-                    .position(|node| {
-                        node.range.start <= range.start && node.range.end >= range.start
-                    })
-                    .unwrap();
-                let end = row.nodes.len()
-                    - row
-                        .nodes
-                        .iter()
-                        .rev()
-                        .position(|node| {
-                            node.range.start <= range.end && node.range.end >= range.end
-                        })
-                        .unwrap();
-
-                &row.nodes[start..end]
-            });
+fn search_grid<R>(
+    state: &State,
+    hood: &Neighborhood,
+    matcher: impl Fn(&Node) -> Option<R>,
+    combiner: impl Fn(R, Vec<&Node>) -> Option<Result<u32, Error>>,
+) -> Result<u32, Error> {
+    let values = state
+        .nodes
+        .iter()
+        .filter_map(|node| matcher(node).map(|value| (node, value)))
+        .filter_map(|(node, value)| {
+            if let Err(err) = aoc::checkpoint() {
+                return Some(Err(err.into()));
+            }
 
-            let rows = [
-                iter.next().unwrap(),
-                iter.next().unwrap(),
-                iter.next().unwrap(),
-            ];
+            combiner(value, state.neighbors(node, hood).collect())
+        });
 
-            (item, rows)
-        })
-        .filter_map(|(item, rows)| combiner(item, rows))
-        .sum::<u32>()
+    checked::try_sum(DAY, values)
 }
 
-fn part1(state: &State) -> u32 {
+fn part1(state: &State, hood: &Neighborhood) -> Result<u32, Error> {
     search_grid(
         state,
+        hood,
         |node| {
             if let Symbol::Number(value) = node.symbol {
                 Some(value)
@@ -154,48 +234,958 @@ fn part1(state: &State) -> u32 {
                 None
             }
         },
-        |value, rows| {
-            rows.into_iter()
-                .flatten()
-                .any(|node| matches!(node.symbol, Symbol::Star | Symbol::Unknown(_)))
-                .then_some(value)
-        },
+        |value, neighbors| touches_symbol(neighbors).then_some(Ok(value)),
     )
 }
 
-fn part2(state: &State) -> u32 {
-    search_grid(
-        state,
-        |node| {
-            if matches!(node.symbol, Symbol::Star) {
-                Some(())
+/// Whether a number with these neighbors is a part number.
+fn touches_symbol<'a>(neighbors: impl IntoIterator<Item = &'a Node>) -> bool {
+    neighbors
+        .into_iter()
+        .any(|node| matches!(node.symbol, Symbol::Star | Symbol::Unknown(_)))
+}
+
+/// How many numbers a gear must touch.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Count {
+    Exactly(usize),
+    AtLeast(usize),
+}
+
+impl Count {
+    fn matches(&self, count: usize) -> bool {
+        match self {
+            Count::Exactly(n) => count == *n,
+            Count::AtLeast(n) => count >= *n,
+        }
+    }
+}
+
+impl std::str::FromStr for Count {
+    type Err = String;
+
+    /// Parses `N` for exactly N or `N+` for at least N.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("invalid gear count `{s}`");
+        match s.strip_suffix('+') {
+            Some(n) => n.parse().map(Self::AtLeast).map_err(|_| invalid()),
+            None => s.parse().map(Self::Exactly).map_err(|_| invalid()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Combine {
+    Product,
+    Sum,
+}
+
+impl std::str::FromStr for Combine {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "product" => Ok(Self::Product),
+            "sum" => Ok(Self::Sum),
+            _ => Err(format!("invalid gear combine `{s}`")),
+        }
+    }
+}
+
+/// Which symbols are gears and how their numbers combine into a ratio. The
+/// default is the puzzle's rule: a `*` touching exactly two numbers, whose
+/// ratio is their product.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct GearRule {
+    symbols: Vec<char>,
+    count: Count,
+    combine: Combine,
+}
+
+impl Default for GearRule {
+    fn default() -> Self {
+        Self {
+            symbols: vec!['*'],
+            count: Count::Exactly(2),
+            combine: Combine::Product,
+        }
+    }
+}
+
+impl GearRule {
+    /// Reads `--gear-symbols CHARS`, `--gear-count N|N+` and `--gear-combine
+    /// product|sum`.
+    fn from_args(args: &mut aoc::Args) -> Result<Self, String> {
+        let mut rule = Self::default();
+
+        if let Some(symbols) = args.value("gear-symbols")? {
+            rule.symbols = symbols.chars().collect();
+            if rule.symbols.is_empty()
+                || rule.symbols.iter().any(|c| *c == '.' || c.is_ascii_digit())
+            {
+                return Err(format!("invalid gear symbols `{symbols}`"));
+            }
+        }
+        if let Some(count) = args.value("gear-count")? {
+            rule.count = count.parse()?;
+        }
+        if let Some(combine) = args.value("gear-combine")? {
+            rule.combine = combine.parse()?;
+        }
+
+        Ok(rule)
+    }
+
+    /// The ratio of a gear symbol touching `numbers`, or `None` if it does
+    /// not touch the required count.
+    fn ratio(&self, numbers: Vec<u32>) -> Option<Result<u32, Overflow>> {
+        if !self.count.matches(numbers.len()) {
+            return None;
+        }
+
+        Some(match self.combine {
+            Combine::Product => checked::product(DAY, numbers),
+            Combine::Sum => checked::sum(DAY, numbers),
+        })
+    }
+
+    fn is_gear_symbol(&self, symbol: Symbol) -> bool {
+        symbol.as_char().is_some_and(|c| self.symbols.contains(&c))
+    }
+}
+
+fn numbers<'a>(neighbors: impl IntoIterator<Item = &'a Node>) -> Vec<u32> {
+    neighbors
+        .into_iter()
+        .filter_map(|node| {
+            if let Symbol::Number(value) = node.symbol {
+                Some(value)
             } else {
                 None
             }
+        })
+        .collect()
+}
+
+fn part2(state: &State, rule: &GearRule, hood: &Neighborhood) -> Result<u32, Error> {
+    search_grid(
+        state,
+        hood,
+        |node| {
+            rule.is_gear_symbol(node.symbol)
+                .then_some(Pos::new(node.row, node.range.start))
         },
-        |_, rows| {
-            let mut iter = rows.into_iter().flatten().filter_map(|node| {
-                if let Symbol::Number(value) = node.symbol {
-                    Some(value)
-                } else {
-                    None
+        |at, neighbors| {
+            let numbers = numbers(neighbors);
+            aoc::debug!("gear symbol at {at}: {numbers:?}");
+
+            Some(rule.ratio(numbers)?.map_err(Error::from))
+        },
+    )
+}
+
+/// The gear symbols that touch some numbers but not as many as `rule`
+/// requires, e.g. stars with one or three numbers under the default rule.
+fn gear_diagnostics<'a>(
+    state: &'a State,
+    rule: &GearRule,
+    hood: &Neighborhood,
+) -> Vec<(&'a Node, Vec<u32>)> {
+    state
+        .nodes
+        .iter()
+        .filter(|node| rule.is_gear_symbol(node.symbol))
+        .map(|node| (node, numbers(state.neighbors(node, hood))))
+        .filter(|(_, numbers)| !numbers.is_empty() && !rule.count.matches(numbers.len()))
+        .collect()
+}
+
+/// What a cell of the schematic turned out to be.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Role {
+    Empty,
+    Part,
+    Loose,
+    Gear,
+    Symbol,
+}
+
+/// The role of every cell, and each gear with its ratio.
+type Annotation = (Grid<Role>, Vec<(Pos, u32)>);
+
+fn annotate(state: &State, rule: &GearRule, hood: &Neighborhood) -> Result<Annotation, Error> {
+    let mut roles = state.grid.map(|_| Role::Empty);
+    let mut ratios = Vec::new();
+
+    for node in &state.nodes {
+        let pos = Pos::new(node.row, node.range.start);
+        let role = match node.symbol {
+            Symbol::Number(_) if touches_symbol(state.neighbors(node, hood)) => Role::Part,
+            Symbol::Number(_) => Role::Loose,
+            symbol if rule.is_gear_symbol(symbol) => {
+                match rule.ratio(numbers(state.neighbors(node, hood))) {
+                    Some(ratio) => {
+                        ratios.push((pos, ratio?));
+                        Role::Gear
+                    }
+                    None => Role::Symbol,
                 }
+            }
+            _ => Role::Symbol,
+        };
+
+        for col in node.range.clone() {
+            roles[Pos::new(node.row, col)] = role;
+        }
+    }
+
+    Ok((roles, ratios))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Render {
+    Ansi,
+    Html,
+}
+
+impl std::str::FromStr for Render {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "ansi" => Ok(Self::Ansi),
+            "html" => Ok(Self::Html),
+            _ => Err(format!("invalid render format `{s}`")),
+        }
+    }
+}
+
+/// Draws the schematic with part numbers in green, other numbers in red,
+/// gears highlighted with their ratio and other symbols dimmed.
+fn render(
+    state: &State,
+    rule: &GearRule,
+    hood: &Neighborhood,
+    format: Render,
+) -> Result<String, Error> {
+    let (roles, ratios) = annotate(state, rule, hood)?;
+
+    let mut runs = vec![Vec::new(); state.grid.height()];
+    for run in roles.runs(|role| Some(*role)) {
+        runs[run.row].push(run);
+    }
+    let mut gears = vec![Vec::new(); state.grid.height()];
+    for (pos, ratio) in &ratios {
+        gears[pos.row].push((*pos, *ratio));
+    }
+    let ratio_at = ratios.iter().copied().collect::<HashMap<_, _>>();
+
+    let mut rows = Vec::new();
+    for ((cells, runs), gears) in state.grid.rows().zip(runs).zip(gears) {
+        let text = |run: &aoc::grid::Run<Role>| cells[run.cols.clone()].iter().collect::<String>();
+
+        rows.push(match format {
+            Render::Ansi => {
+                let mut line = runs
+                    .iter()
+                    .map(|run| match run.key {
+                        Role::Empty => text(run),
+                        Role::Part => format!("\x1b[32m{}\x1b[0m", text(run)),
+                        Role::Loose => format!("\x1b[31m{}\x1b[0m", text(run)),
+                        Role::Gear => format!("\x1b[1;30;43m{}\x1b[0m", text(run)),
+                        Role::Symbol => format!("\x1b[2m{}\x1b[0m", text(run)),
+                    })
+                    .collect::<String>();
+                for (pos, ratio) in gears {
+                    line.push_str(&format!("  \x1b[33mgear {pos} = {ratio}\x1b[0m"));
+                }
+                line
+            }
+            Render::Html => runs
+                .iter()
+                .map(|run| {
+                    let text = html_escape(&text(run));
+                    match run.key {
+                        Role::Empty => text,
+                        Role::Part => format!(r#"<span class="part">{text}</span>"#),
+                        Role::Loose => format!(r#"<span class="loose">{text}</span>"#),
+                        Role::Gear => run
+                            .positions()
+                            .map(|pos| {
+                                // Ratios are keyed by the gear's first cell.
+                                let gear = state.owners[pos].map(|idx| &state.nodes[idx]);
+                                let start =
+                                    gear.map_or(pos, |gear| Pos::new(gear.row, gear.range.start));
+                                let ratio = ratio_at.get(&start).copied().unwrap_or_default();
+                                format!(
+                                    r#"<span class="gear" title="gear {start} = {ratio}">{}</span>"#,
+                                    html_escape(&state.grid[pos].to_string())
+                                )
+                            })
+                            .collect(),
+                        Role::Symbol => format!(r#"<span class="symbol">{text}</span>"#),
+                    }
+                })
+                .collect(),
+        });
+    }
+
+    Ok(match format {
+        Render::Ansi => rows.join("\n"),
+        Render::Html => {
+            let gears = ratios
+                .iter()
+                .map(|(pos, ratio)| format!("<li>gear {pos} = {ratio}</li>"))
+                .collect::<String>();
+
+            format!(
+                r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>Day {DAY} schematic</title>
+<style>
+body {{ background: #111; color: #ccc; font-family: monospace; }}
+.part {{ color: #4c4; }}
+.loose {{ color: #e44; }}
+.gear {{ background: #fc3; color: #111; font-weight: bold; }}
+.symbol {{ opacity: 0.5; }}
+</style>
+</head>
+<body>
+<pre>
+{}
+</pre>
+<ul>
+{gears}
+</ul>
+</body>
+</html>"#,
+                rows.join("\n")
+            )
+        }
+    })
+}
+
+fn html_escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum GraphFormat {
+    Dot,
+    Json,
+}
+
+impl std::str::FromStr for GraphFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "dot" => Ok(Self::Dot),
+            "json" => Ok(Self::Json),
+            _ => Err(format!("invalid graph format `{s}`")),
+        }
+    }
+}
+
+/// The bipartite graph of numbers and the symbols they touch. Nodes are
+/// named `n<idx>` for numbers and `s<idx>` for symbols, by their index in
+/// `state.nodes`, and positions are 1-based with inclusive column spans.
+fn graph(state: &State, hood: &Neighborhood, format: GraphFormat) -> String {
+    let name = |idx: usize| match state.nodes[idx].symbol {
+        Symbol::Number(_) => format!("n{idx}"),
+        _ => format!("s{idx}"),
+    };
+
+    let edges = state
+        .nodes
+        .iter()
+        .enumerate()
+        .filter(|(_, node)| matches!(node.symbol, Symbol::Number(_)))
+        .flat_map(|(idx, node)| {
+            state
+                .neighbor_indices(node, hood)
+                .into_iter()
+                .filter(|other| state.nodes[*other].symbol.as_char().is_some())
+                .map(move |other| (idx, other))
+        })
+        .collect::<Vec<_>>();
+
+    match format {
+        GraphFormat::Dot => {
+            let mut dot = format!("graph day{DAY} {{\n");
+            for (idx, node) in state.nodes.iter().enumerate() {
+                let (label, shape) = match node.symbol {
+                    Symbol::Number(value) => (value.to_string(), "box"),
+                    symbol => (symbol.as_char().unwrap_or_default().to_string(), "circle"),
+                };
+                // Graphviz positions are `x,y` with y growing upwards, and
+                // `!` pins the node there for `neato`.
+                let pos = Pos::new(node.row, node.range.start);
+                dot.push_str(&format!(
+                    "  {} [label=\"{}\", shape={shape}, pos=\"{},-{}!\", tooltip=\"{pos}\"];\n",
+                    name(idx),
+                    label.replace('\\', "\\\\").replace('"', "\\\""),
+                    pos.col + 1,
+                    pos.row + 1,
+                ));
+            }
+            for (number, symbol) in edges {
+                dot.push_str(&format!("  {} -- {};\n", name(number), name(symbol)));
+            }
+            dot.push('}');
+            dot
+        }
+        GraphFormat::Json => {
+            let (numbers, symbols) = state
+                .nodes
+                .iter()
+                .enumerate()
+                .partition::<Vec<_>, _>(|(_, node)| matches!(node.symbol, Symbol::Number(_)));
+            let numbers = numbers
+                .into_iter()
+                .map(|(idx, node)| {
+                    let Symbol::Number(value) = node.symbol else {
+                        unreachable!()
+                    };
+                    Value::Object(vec![
+                        ("id".to_string(), name(idx).into()),
+                        ("value".to_string(), Value::from(value as i64)),
+                        ("row".to_string(), Value::from(node.row as i64 + 1)),
+                        (
+                            "cols".to_string(),
+                            Value::Array(vec![
+                                Value::from(node.range.start as i64 + 1),
+                                Value::from(node.range.end as i64),
+                            ]),
+                        ),
+                    ])
+                })
+                .collect();
+            let symbols = symbols
+                .into_iter()
+                .map(|(idx, node)| {
+                    Value::Object(vec![
+                        ("id".to_string(), name(idx).into()),
+                        (
+                            "char".to_string(),
+                            node.symbol.as_char().unwrap_or_default().to_string().into(),
+                        ),
+                        ("row".to_string(), Value::from(node.row as i64 + 1)),
+                        ("col".to_string(), Value::from(node.range.start as i64 + 1)),
+                    ])
+                })
+                .collect();
+            let edges = edges
+                .into_iter()
+                .map(|(number, symbol)| {
+                    Value::Array(vec![name(number).into(), name(symbol).into()])
+                })
+                .collect();
+
+            Value::Object(vec![
+                ("numbers".to_string(), Value::Array(numbers)),
+                ("symbols".to_string(), Value::Array(symbols)),
+                ("edges".to_string(), Value::Array(edges)),
+            ])
+            .to_string()
+        }
+    }
+}
+
+/// A number of the schematic with the symbols it touches.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Entry {
+    value: u32,
+    row: usize,
+    cols: std::ops::Range<usize>,
+    is_part: bool,
+    symbols: Vec<(char, Pos)>,
+}
+
+/// What is at a position of the schematic, see [`Inventory::at`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Found<'a> {
+    Number(&'a Entry),
+    /// A symbol and the numbers touching it.
+    Symbol(char, Vec<&'a Entry>),
+}
+
+/// An index of every number of a schematic, built once from the node spans
+/// and the chosen neighborhood.
+struct Inventory<'a> {
+    state: &'a State,
+    /// The numbers in reading order.
+    entries: Vec<Entry>,
+    /// The index in `entries` of each number node.
+    numbers: HashMap<usize, usize>,
+}
+
+impl<'a> Inventory<'a> {
+    fn new(state: &'a State, hood: &Neighborhood) -> Self {
+        let mut entries = Vec::new();
+        let mut numbers = HashMap::new();
+
+        for (idx, node) in state.nodes.iter().enumerate() {
+            let Symbol::Number(value) = node.symbol else {
+                continue;
+            };
+
+            let symbols = state
+                .neighbors(node, hood)
+                .filter_map(|other| {
+                    Some((
+                        other.symbol.as_char()?,
+                        Pos::new(other.row, other.range.start),
+                    ))
+                })
+                .collect::<Vec<_>>();
+
+            numbers.insert(idx, entries.len());
+            entries.push(Entry {
+                value,
+                row: node.row,
+                cols: node.range.clone(),
+                is_part: !symbols.is_empty(),
+                symbols,
             });
+        }
 
-            let first = iter.next()?;
-            let second = iter.next()?;
+        Self {
+            state,
+            entries,
+            numbers,
+        }
+    }
 
-            Some(first * second)
-        },
-    )
+    fn numbers(&self) -> &[Entry] {
+        &self.entries
+    }
+
+    /// The number or symbol covering `pos`, or `None` for an empty cell or a
+    /// position outside the schematic.
+    fn at(&self, pos: Pos) -> Option<Found<'_>> {
+        let idx = (*self.state.owners.get(pos)?)?;
+        if let Some(entry) = self.numbers.get(&idx) {
+            return Some(Found::Number(&self.entries[*entry]));
+        }
+
+        // Entries record a symbol by the start of its node, whichever of
+        // its cells they touch.
+        let node = &self.state.nodes[idx];
+        let symbol = node.symbol.as_char()?;
+        let start = Pos::new(node.row, node.range.start);
+        let touching = self
+            .entries
+            .iter()
+            .filter(|entry| entry.symbols.contains(&(symbol, start)))
+            .collect();
+        Some(Found::Symbol(symbol, touching))
+    }
+
+    /// The numbers touching at least one `symbol`.
+    fn by_symbol(&self, symbol: char) -> impl Iterator<Item = &Entry> {
+        self.entries
+            .iter()
+            .filter(move |entry| entry.symbols.iter().any(|(c, _)| *c == symbol))
+    }
+}
+
+/// Lists `entries` as a table of values, positions, whether they are part
+/// numbers and which symbols they touch.
+fn inventory_table<'a>(entries: impl IntoIterator<Item = &'a Entry>) -> String {
+    let mut rows = vec![["number", "at", "part", "symbols"]
+        .map(String::from)
+        .to_vec()];
+    rows.extend(entries.into_iter().map(|entry| {
+        let start = Pos::new(entry.row, entry.cols.start);
+        let symbols = entry
+            .symbols
+            .iter()
+            .map(|(symbol, pos)| format!("{symbol} {pos}"))
+            .collect::<Vec<_>>();
+
+        vec![
+            entry.value.to_string(),
+            format!("{start}-{}", entry.cols.end),
+            if entry.is_part { "yes" } else { "no" }.to_string(),
+            symbols.join(", "),
+        ]
+    }));
+
+    aoc::table::render(&rows)
+}
+
+/// How the meaning of a schematic changed: which numbers became or stopped
+/// being part numbers, which gears appeared, disappeared or changed ratio,
+/// and both totals before and after.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Diff {
+    new_parts: Vec<Entry>,
+    lost_parts: Vec<Entry>,
+    new_gears: Vec<(Pos, u32)>,
+    lost_gears: Vec<(Pos, u32)>,
+    /// Gears in both schematics, with their old and new ratio.
+    changed_gears: Vec<(Pos, u32, u32)>,
+    part1: (u32, u32),
+    part2: (u32, u32),
+}
+
+impl Diff {
+    /// Numbers are matched by value and span, gears by position.
+    fn new(old: &State, new: &State, rule: &GearRule, hood: &Neighborhood) -> Result<Self, Error> {
+        let parts = |state| {
+            Inventory::new(state, hood)
+                .numbers()
+                .iter()
+                .filter(|entry| entry.is_part)
+                .cloned()
+                .collect::<Vec<_>>()
+        };
+        let (old_parts, new_parts) = (parts(old), parts(new));
+        let key = |entry: &Entry| (entry.value, entry.row, entry.cols.clone());
+        let missing = |parts: &[Entry], from: &[Entry]| {
+            let from = from.iter().map(key).collect::<HashSet<_>>();
+            parts
+                .iter()
+                .filter(|part| !from.contains(&key(part)))
+                .cloned()
+                .collect::<Vec<_>>()
+        };
+
+        let (_, old_gears) = annotate(old, rule, hood)?;
+        let (_, new_gears) = annotate(new, rule, hood)?;
+        let (old_ratios, new_ratios) = (
+            old_gears.iter().copied().collect::<HashMap<_, _>>(),
+            new_gears.iter().copied().collect::<HashMap<_, _>>(),
+        );
+        let changed_gears = old_gears
+            .iter()
+            .filter_map(|(pos, old)| {
+                let new = new_ratios.get(pos)?;
+                (new != old).then_some((*pos, *old, *new))
+            })
+            .collect();
+
+        Ok(Self {
+            new_parts: missing(&new_parts, &old_parts),
+            lost_parts: missing(&old_parts, &new_parts),
+            new_gears: new_gears
+                .iter()
+                .filter(|(pos, _)| !old_ratios.contains_key(pos))
+                .copied()
+                .collect(),
+            lost_gears: old_gears
+                .iter()
+                .filter(|(pos, _)| !new_ratios.contains_key(pos))
+                .copied()
+                .collect(),
+            changed_gears,
+            part1: (part1(old, hood)?, part1(new, hood)?),
+            part2: (part2(old, rule, hood)?, part2(new, rule, hood)?),
+        })
+    }
+}
+
+/// One line per change, `+` for what the new schematic gained and `-` for
+/// what it lost, after both totals.
+impl std::fmt::Display for Diff {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (part, (old, new)) in [(1, self.part1), (2, self.part2)] {
+            let change = new as i64 - old as i64;
+            writeln!(f, "part {part}: {old} -> {new} ({change:+})")?;
+        }
+
+        let span = |entry: &Entry| {
+            format!(
+                "{}-{}",
+                Pos::new(entry.row, entry.cols.start),
+                entry.cols.end
+            )
+        };
+        for entry in &self.new_parts {
+            writeln!(f, "+ part number {} at {}", entry.value, span(entry))?;
+        }
+        for entry in &self.lost_parts {
+            writeln!(f, "- part number {} at {}", entry.value, span(entry))?;
+        }
+        for (pos, ratio) in &self.new_gears {
+            writeln!(f, "+ gear at {pos} = {ratio}")?;
+        }
+        for (pos, ratio) in &self.lost_gears {
+            writeln!(f, "- gear at {pos} = {ratio}")?;
+        }
+        for (pos, old, new) in &self.changed_gears {
+            writeln!(f, "~ gear at {pos} = {old} -> {new}")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Solves both parts as rows arrive, holding only the rows within reach of
+/// the row being finished: three rows for the puzzle's neighborhood.
+struct Stream {
+    rule: GearRule,
+    hood: Neighborhood,
+    /// The numbers and symbols of the rows still in reach, one entry per
+    /// row, each parsed once as it arrives.
+    window: std::collections::VecDeque<Vec<Node>>,
+    /// How many rows have arrived.
+    rows: usize,
+    /// How many rows have had their numbers and symbols counted.
+    finished: usize,
+    part1: u32,
+    part2: u32,
+}
+
+impl Stream {
+    /// Fails for a toroidal neighborhood, which needs the last rows to finish
+    /// the first.
+    fn new(rule: GearRule, hood: Neighborhood) -> Result<Self, String> {
+        if hood.toroidal {
+            return Err("--stream does not support --toroidal".to_string());
+        }
+
+        Ok(Self {
+            rule,
+            hood,
+            window: std::collections::VecDeque::new(),
+            rows: 0,
+            finished: 0,
+            part1: 0,
+            part2: 0,
+        })
+    }
+
+    /// Lines may differ in width: like [`State::parse`] pads them, the cells
+    /// past the end of a line are empty.
+    fn push(&mut self, line: &str) -> Result<(), Error> {
+        let mut nodes = parse_nodes(&Grid::from_lines_padded([line], '.', |c| c))?;
+        for node in &mut nodes {
+            node.row = self.rows;
+        }
+
+        self.window.push_back(nodes);
+        self.rows += 1;
+
+        while self.finished + self.hood.radius < self.rows {
+            self.finish_row()?;
+        }
+
+        let first = self.rows - self.window.len();
+        let keep = self.finished.saturating_sub(self.hood.radius);
+        self.window.drain(..keep.saturating_sub(first));
+
+        Ok(())
+    }
+
+    /// The nodes in the window within the neighborhood of `node`, in reading
+    /// order.
+    fn neighbors(&self, node: &Node) -> Vec<&Node> {
+        let first = self.rows - self.window.len();
+
+        let mut neighbors = Vec::new();
+        for (rows, cols) in self.hood.offsets() {
+            for col in node.range.clone() {
+                let (Some(row), Some(col)) = (
+                    node.row.checked_add_signed(rows),
+                    col.checked_add_signed(cols),
+                ) else {
+                    continue;
+                };
+                let Some(nodes) = row.checked_sub(first).and_then(|row| self.window.get(row))
+                else {
+                    continue;
+                };
+
+                let idx = nodes.partition_point(|other| other.range.end <= col);
+                if let Some(other) = nodes.get(idx).filter(|other| other.range.contains(&col)) {
+                    if (other.row, other.range.start) != (node.row, node.range.start) {
+                        neighbors.push(other);
+                    }
+                }
+            }
+        }
+
+        neighbors.sort_by_key(|other| (other.row, other.range.start));
+        neighbors.dedup_by_key(|other| (other.row, other.range.start));
+        neighbors
+    }
+
+    /// Counts the numbers and symbols of the next unfinished row, which must
+    /// have every row it can touch in the window.
+    fn finish_row(&mut self) -> Result<(), Error> {
+        aoc::checkpoint()?;
+
+        let (mut part1, mut part2) = (self.part1, self.part2);
+        let first = self.rows - self.window.len();
+        for node in &self.window[self.finished - first] {
+            match node.symbol {
+                Symbol::Number(value) if touches_symbol(self.neighbors(node)) => {
+                    part1 = checked::add(DAY, part1, value)?;
+                }
+                symbol if self.rule.is_gear_symbol(symbol) => {
+                    if let Some(ratio) = self.rule.ratio(numbers(self.neighbors(node))) {
+                        part2 = checked::add(DAY, part2, ratio?)?;
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        (self.part1, self.part2) = (part1, part2);
+        self.finished += 1;
+        Ok(())
+    }
+
+    /// Finishes the remaining rows and returns the answers to both parts.
+    fn finish(mut self) -> Result<(u32, u32), Error> {
+        while self.finished < self.rows {
+            self.finish_row()?;
+        }
+
+        Ok((self.part1, self.part2))
+    }
 }
 
-fn main() {
-    let state = aoc::get_input();
+fn main() -> std::process::ExitCode {
+    let mut args = aoc::Args::from_env();
+    let (rule, hood) = match GearRule::from_args(&mut args)
+        .and_then(|rule| Ok((rule, Neighborhood::from_args(&mut args)?)))
+    {
+        Ok(config) => config,
+        Err(err) => return aoc::runner::usage_error(DAY, err),
+    };
+
+    let format = args
+        .value("render")
+        .and_then(|format| format.map(|format| format.parse::<Render>()).transpose());
+    match format {
+        Err(err) => return aoc::runner::usage_error(DAY, err),
+        Ok(Some(format)) => {
+            return aoc::runner::run_mode(DAY, args, State::parse, move |state: &State| {
+                render(state, &rule, &hood, format)
+            });
+        }
+        Ok(None) => {}
+    }
+
+    let format = args.value("graph").and_then(|format| {
+        format
+            .map(|format| format.parse::<GraphFormat>())
+            .transpose()
+    });
+    match format {
+        Err(err) => return aoc::runner::usage_error(DAY, err),
+        Ok(Some(format)) => {
+            return aoc::runner::run_mode(DAY, args, State::parse, move |state: &State| {
+                Ok::<_, Error>(graph(state, &hood, format))
+            });
+        }
+        Ok(None) => {}
+    }
+
+    if args.command("query") {
+        let query = args
+            .value("at")
+            .and_then(|at| at.map(|at| at.parse::<Pos>()).transpose())
+            .and_then(|at| {
+                let symbol = match args.value("symbol")? {
+                    Some(symbol) if symbol.chars().count() == 1 => symbol.chars().next(),
+                    Some(symbol) => return Err(format!("invalid symbol `{symbol}`")),
+                    None => None,
+                };
+                Ok((at, symbol))
+            });
+        let (at, symbol) = match query {
+            Ok(query) => query,
+            Err(err) => return aoc::runner::usage_error(DAY, err),
+        };
+
+        return aoc::runner::run_mode(DAY, args, State::parse, move |state: &State| {
+            let inventory = Inventory::new(state, &hood);
+            Ok::<_, Error>(match (at, symbol) {
+                (Some(pos), _) => match inventory.at(pos) {
+                    Some(Found::Number(entry)) => inventory_table([entry]),
+                    Some(Found::Symbol(symbol, entries)) => format!(
+                        "{symbol} at {pos} touches {} number(s)\n{}",
+                        entries.len(),
+                        inventory_table(entries)
+                    ),
+                    None => format!("nothing at {pos}"),
+                },
+                (None, Some(symbol)) => inventory_table(inventory.by_symbol(symbol)),
+                (None, None) => inventory_table(inventory.numbers()),
+            })
+        });
+    }
+
+    let old = match args.value("diff").and_then(|path| {
+        path.map(|path| {
+            std::fs::read_to_string(&path)
+                .map_err(|err| format!("cannot read {path}: {err}"))
+                .and_then(|contents| State::parse(contents.lines()).map_err(|err| err.to_string()))
+        })
+        .transpose()
+    }) {
+        Ok(old) => old,
+        Err(err) => return aoc::runner::usage_error(DAY, err),
+    };
+    if let Some(old) = old {
+        return aoc::runner::run_mode(DAY, args, State::parse, move |new: &State| {
+            Diff::new(&old, new, &rule, &hood).map(|diff| diff.to_string().trim_end().to_string())
+        });
+    }
+
+    if args.flag("stream") {
+        let mut stream = match Stream::new(rule.clone(), hood) {
+            Ok(stream) => stream,
+            Err(err) => return aoc::runner::usage_error(DAY, err),
+        };
+
+        return aoc::runner::run_task(DAY, args, move || {
+            for line in std::io::stdin().lock().lines() {
+                let line = line.map_err(|err| format!("cannot read stdin: {err}"))?;
+                stream.push(&line).map_err(|err| err.to_string())?;
+            }
+
+            let (part1, part2) = stream.finish().map_err(|err| err.to_string())?;
+            Ok::<_, String>(format!("part 1: {part1}\npart 2: {part2}"))
+        });
+    }
 
-    println!("part 1: {}", part1(&state));
+    if args.flag("diagnose-gears") {
+        return aoc::runner::run_mode(DAY, args, State::parse, move |state: &State| {
+            Ok::<_, Error>(
+                gear_diagnostics(state, &rule, &hood)
+                    .into_iter()
+                    .map(|(node, numbers)| {
+                        format!(
+                            "{} at {} touches {} number{}: {numbers:?}",
+                            node.symbol.as_char().unwrap_or_default(),
+                            Pos::new(node.row, node.range.start),
+                            numbers.len(),
+                            if numbers.len() == 1 { "" } else { "s" },
+                        )
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\n"),
+            )
+        });
+    }
 
-    println!("part 2: {}", part2(&state));
+    aoc::runner::run_parsed(
+        DAY,
+        args,
+        State::parse,
+        move |state: &State| part1(state, &hood),
+        move |state: &State| part2(state, &rule, &hood),
+    )
 }
 
 #[cfg(test)]
@@ -205,31 +1195,370 @@ mod tests {
     const INPUT: &str = include_str!("../inputs/day3");
     const EXAMPLE_INPUT: &str = include_str!("../examples/day3");
 
+    /// Streams `lines` through a [`Stream`] and returns both answers.
+    fn stream<S: AsRef<str>>(
+        lines: impl IntoIterator<Item = S>,
+        rule: &GearRule,
+        hood: &Neighborhood,
+    ) -> Result<(u32, u32), Error> {
+        let mut stream = Stream::new(rule.clone(), *hood).unwrap();
+        for line in lines {
+            stream.push(line.as_ref())?;
+        }
+        stream.finish()
+    }
+
     #[test]
     fn test_example_part_1() {
         let state = aoc::get_input_from(EXAMPLE_INPUT);
 
-        assert_eq!(part1(&state), 4361);
+        assert_eq!(part1(&state, &Neighborhood::default()), Ok(4361));
     }
 
     #[test]
     fn test_example_part_2() {
         let state = aoc::get_input_from(EXAMPLE_INPUT);
 
-        assert_eq!(part2(&state), 467835);
+        assert_eq!(
+            part2(&state, &GearRule::default(), &Neighborhood::default()),
+            Ok(467835)
+        );
+    }
+
+    #[cfg(feature = "checked")]
+    #[test]
+    fn test_overflow() {
+        let overflow = Error::Overflow(Overflow {
+            day: DAY,
+            op: checked::Op::Add,
+            lhs: 4294967290,
+            rhs: 6,
+        });
+
+        assert_eq!(State::parse(["4294967296*"]).unwrap_err(), overflow);
+        assert_eq!(
+            stream(
+                ["1.", "4294967296*"],
+                &GearRule::default(),
+                &Neighborhood::default()
+            ),
+            Err(overflow)
+        );
+    }
+
+    #[test]
+    fn test_parse() {
+        let state = aoc::get_input_from::<State>("12..\n3\n..#");
+        assert_eq!((state.grid.width(), state.grid.height()), (4, 3));
+        assert_eq!(state.grid.row(1), Some(&['3', '.', '.', '.'][..]));
+        assert_eq!(part1(&state, &Neighborhood::default()), Ok(0));
+
+        let state = aoc::get_input_from::<State>("1**2\n.##.");
+        let symbols = state
+            .nodes
+            .iter()
+            .map(|node| (node.symbol, node.range.clone()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            symbols,
+            [
+                (Symbol::Number(1), 0..1),
+                (Symbol::Star, 1..3),
+                (Symbol::Number(2), 3..4),
+                (Symbol::Unknown('#'), 1..3),
+            ]
+        );
+        assert_eq!(
+            part2(&state, &GearRule::default(), &Neighborhood::default()),
+            Ok(2)
+        );
+    }
+
+    #[test]
+    fn test_gear_rules() {
+        let state = aoc::get_input_from::<State>("1.2\n.*.\n3#.\n..4");
+
+        assert_eq!(
+            part2(&state, &GearRule::default(), &Neighborhood::default()),
+            Ok(0)
+        );
+
+        let at_least = GearRule {
+            count: "2+".parse().unwrap(),
+            ..Default::default()
+        };
+        assert_eq!(part2(&state, &at_least, &Neighborhood::default()), Ok(6));
+
+        let sum = GearRule {
+            symbols: vec!['#'],
+            combine: Combine::Sum,
+            ..Default::default()
+        };
+        assert_eq!(part2(&state, &sum, &Neighborhood::default()), Ok(3 + 4));
+
+        let diagnostics = gear_diagnostics(&state, &GearRule::default(), &Neighborhood::default())
+            .into_iter()
+            .map(|(node, numbers)| (node.row, node.range.start, numbers))
+            .collect::<Vec<_>>();
+        assert_eq!(diagnostics, [(1, 1, vec![1, 2, 3])]);
+    }
+
+    #[test]
+    fn test_neighborhoods() {
+        let state = aoc::get_input_from::<State>(EXAMPLE_INPUT);
+        let rule = GearRule::default();
+
+        let von_neumann = Neighborhood {
+            shape: Shape::VonNeumann,
+            ..Neighborhood::default()
+        };
+        assert_eq!(part1(&state, &von_neumann), Ok(35 + 633 + 617 + 664 + 598));
+        assert_eq!(part2(&state, &rule, &von_neumann), Ok(0));
+
+        let wide = Neighborhood {
+            radius: 2,
+            ..Neighborhood::default()
+        };
+        assert_eq!(part1(&state, &wide), Ok(4361 + 114 + 58));
+        assert_eq!(part2(&state, &rule, &wide), Ok(0));
+
+        let toroidal = Neighborhood {
+            toroidal: true,
+            ..Neighborhood::default()
+        };
+        assert_eq!(part1(&state, &toroidal), Ok(4361));
+        assert_eq!(part2(&state, &rule, &toroidal), Ok(467835));
+
+        let state = aoc::get_input_from::<State>("1.2\n...\n*..");
+        assert_eq!(part1(&state, &Neighborhood::default()), Ok(0));
+        assert_eq!(part1(&state, &toroidal), Ok(3));
+        assert_eq!(part2(&state, &rule, &toroidal), Ok(2));
+    }
+
+    #[test]
+    fn test_stream() {
+        let rule = GearRule::default();
+        let radius = Neighborhood {
+            radius: 2,
+            ..Neighborhood::default()
+        };
+        let von_neumann = Neighborhood {
+            shape: Shape::VonNeumann,
+            ..Neighborhood::default()
+        };
+
+        let mut rng = aoc::rng::Rng::new(3);
+        let generated = (0..500)
+            .map(|_| {
+                let width = 20 + rng.below(21) as usize;
+                let mut line = String::new();
+                while line.len() < width {
+                    match rng.below(6) {
+                        0 => line.push_str(&format!("{}.", rng.range(1..=999))),
+                        1 => line.push(b"*#+$"[rng.below(4) as usize] as char),
+                        _ => line.push('.'),
+                    }
+                }
+                line[..width].to_string()
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        for input in [EXAMPLE_INPUT, INPUT, &generated] {
+            let state = aoc::get_input_from::<State>(input);
+            for hood in [Neighborhood::default(), radius, von_neumann] {
+                let expected = (part1(&state, &hood), part2(&state, &rule, &hood));
+                let streamed = stream(input.lines(), &rule, &hood).unwrap();
+                assert_eq!((Ok(streamed.0), Ok(streamed.1)), expected);
+            }
+        }
+
+        assert!(Stream::new(
+            rule.clone(),
+            Neighborhood {
+                toroidal: true,
+                ..radius
+            }
+        )
+        .is_err());
+
+        let mut window = Stream::new(rule.clone(), radius).unwrap();
+        for line in INPUT.lines() {
+            window.push(line).unwrap();
+            assert!(window.window.len() <= 2 * radius.radius + 1);
+        }
+    }
+
+    #[test]
+    fn test_inventory() {
+        let state = aoc::get_input_from::<State>(EXAMPLE_INPUT);
+        let inventory = Inventory::new(&state, &Neighborhood::default());
+
+        assert_eq!(inventory.numbers().len(), 10);
+        assert_eq!(
+            inventory
+                .numbers()
+                .iter()
+                .filter(|entry| !entry.is_part)
+                .count(),
+            2
+        );
+
+        let Some(Found::Number(entry)) = inventory.at(Pos::new(2, 7)) else {
+            panic!("expected a number at 3:8");
+        };
+        assert_eq!((entry.value, entry.cols.clone()), (633, 6..9));
+        assert_eq!(entry.symbols, [('#', Pos::new(3, 6))]);
+
+        let Some(Found::Symbol('*', entries)) = inventory.at(Pos::new(1, 3)) else {
+            panic!("expected a star at 2:4");
+        };
+        assert_eq!(
+            entries.iter().map(|entry| entry.value).collect::<Vec<_>>(),
+            [467, 35]
+        );
+        assert_eq!(inventory.at(Pos::new(0, 3)), None);
+        assert_eq!(inventory.at(Pos::new(10, 0)), None);
+
+        let stars = inventory.by_symbol('*').map(|entry| entry.value);
+        assert_eq!(stars.collect::<Vec<_>>(), [467, 35, 617, 755, 598]);
+
+        let table = inventory_table(inventory.by_symbol('$'));
+        assert_eq!(
+            table,
+            "number  at      part  symbols\n664     10:2-4  yes   $ 9:4"
+        );
+
+        let state = aoc::get_input_from::<State>("1..\n.&&\n..2");
+        let inventory = Inventory::new(&state, &Neighborhood::default());
+        for col in [1, 2] {
+            let Some(Found::Symbol('&', entries)) = inventory.at(Pos::new(1, col)) else {
+                panic!("expected a symbol at 2:{}", col + 1);
+            };
+            assert_eq!(
+                entries.iter().map(|entry| entry.value).collect::<Vec<_>>(),
+                [1, 2]
+            );
+        }
+    }
+
+    #[test]
+    fn test_diff() {
+        let old = aoc::get_input_from::<State>(EXAMPLE_INPUT);
+        let new = EXAMPLE_INPUT
+            .replacen("467..114..", "467..114#.", 1)
+            .replacen("...*......", "..........", 1)
+            .replacen("...$.*....", "...$.*..*.", 1);
+        let new = aoc::get_input_from::<State>(&new);
+        let diff = Diff::new(&old, &new, &GearRule::default(), &Neighborhood::default()).unwrap();
+
+        assert_eq!(
+            diff.to_string(),
+            "part 1: 4361 -> 3973 (-388)\n\
+             part 2: 467835 -> 902980 (+435145)\n\
+             + part number 114 at 1:6-8\n\
+             - part number 467 at 1:1-3\n\
+             - part number 35 at 3:3-4\n\
+             + gear at 9:9 = 451490\n\
+             - gear at 2:4 = 16345\n"
+        );
+
+        let same = Diff::new(&old, &old, &GearRule::default(), &Neighborhood::default()).unwrap();
+        assert!(same.new_parts.is_empty() && same.lost_gears.is_empty());
+        assert_eq!(same.part1, (4361, 4361));
+    }
+
+    #[test]
+    fn test_render() {
+        let state = aoc::get_input_from::<State>(EXAMPLE_INPUT);
+        let (roles, ratios) =
+            annotate(&state, &GearRule::default(), &Neighborhood::default()).unwrap();
+
+        assert_eq!(roles[Pos::new(0, 0)], Role::Part);
+        assert_eq!(roles[Pos::new(0, 5)], Role::Loose);
+        assert_eq!(roles[Pos::new(1, 3)], Role::Gear);
+        assert_eq!(roles[Pos::new(4, 3)], Role::Symbol);
+        assert_eq!(ratios, [(Pos::new(1, 3), 16345), (Pos::new(8, 5), 451490)]);
+
+        let ansi = render(
+            &state,
+            &GearRule::default(),
+            &Neighborhood::default(),
+            Render::Ansi,
+        )
+        .unwrap();
+        assert!(ansi.starts_with("\x1b[32m467\x1b[0m..\x1b[31m114\x1b[0m.."));
+        assert!(ansi.contains("gear 2:4 = 16345"));
+
+        let html = render(
+            &state,
+            &GearRule::default(),
+            &Neighborhood::default(),
+            Render::Html,
+        )
+        .unwrap();
+        assert!(html.contains(r#"<span class="gear" title="gear 9:6 = 451490">*</span>"#));
+        assert!(html.contains(r#".<span class="symbol">$</span>."#));
+
+        let wide = aoc::get_input_from::<State>("1..\n.**\n..2");
+        let wide_html = render(
+            &wide,
+            &GearRule::default(),
+            &Neighborhood::default(),
+            Render::Html,
+        )
+        .unwrap();
+        assert_eq!(
+            wide_html
+                .matches(r#"<span class="gear" title="gear 2:2 = 2">*</span>"#)
+                .count(),
+            2
+        );
+    }
+
+    #[test]
+    fn test_graph() {
+        let state = aoc::get_input_from::<State>(EXAMPLE_INPUT);
+
+        let json =
+            Value::parse(&graph(&state, &Neighborhood::default(), GraphFormat::Json)).unwrap();
+        let len = |key| json.get(key).and_then(Value::as_array).unwrap().len();
+        assert_eq!((len("numbers"), len("symbols"), len("edges")), (10, 6, 8));
+        assert_eq!(
+            json.get("numbers").and_then(Value::as_array).unwrap()[0].to_string(),
+            r#"{"id":"n0","value":467,"row":1,"cols":[1,3]}"#
+        );
+        assert_eq!(
+            json.get("symbols").and_then(Value::as_array).unwrap()[0].to_string(),
+            r#"{"id":"s2","char":"*","row":2,"col":4}"#
+        );
+        assert_eq!(
+            json.get("edges").and_then(Value::as_array).unwrap()[0].to_string(),
+            r#"["n0","s2"]"#
+        );
+
+        let dot = graph(&state, &Neighborhood::default(), GraphFormat::Dot);
+        assert!(dot.starts_with(
+            "graph day3 {\n  n0 [label=\"467\", shape=box, pos=\"1,-1!\", tooltip=\"1:1\"];"
+        ));
+        assert!(dot.contains("  n0 -- s2;\n"));
+        assert_eq!(dot.matches(" -- ").count(), 8);
     }
 
     #[test]
     fn test_part_1() {
         let state = aoc::get_input_from(INPUT);
 
-        assert_eq!(part1(&state), 530849);
+        assert_eq!(part1(&state, &Neighborhood::default()), Ok(530849));
     }
 
     #[test]
     fn test_part_2() {
         let state = aoc::get_input_from(INPUT);
 
-        assert_eq!(part2(&state), 84900879);
+        assert_eq!(
+            part2(&state, &GearRule::default(), &Neighborhood::default()),
+            Ok(84900879)
+        );
     }
 }
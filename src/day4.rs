@@ -1,22 +1,169 @@
 use std::collections::HashSet;
 
+use aoc::{checked, Error};
+
+const DAY: u8 = 4;
+
 #[derive(Debug)]
 struct State {
     cards: Vec<Card>,
 }
 
+impl State {
+    /// Parses one card per line. Problems that leave no card to read, like a
+    /// missing `|`, always fail. The others are returned as warnings, with
+    /// the affected numbers skipped as before, unless `mode` is strict.
+    fn parse<S: AsRef<str>>(
+        lines: impl IntoIterator<Item = S>,
+        mode: Mode,
+    ) -> Result<(Self, Vec<Diagnostic>), Vec<Diagnostic>> {
+        let mut cards = Vec::new();
+        let mut diagnostics = Vec::new();
+        let mut fatal = false;
+        let mut expected: Option<[usize; 2]> = None;
+
+        for (row, line) in lines.into_iter().enumerate() {
+            let parsed = match Card::parse(line.as_ref(), row) {
+                Ok(parsed) => parsed,
+                Err(diagnostic) => {
+                    diagnostics.push(diagnostic);
+                    fatal = true;
+                    continue;
+                }
+            };
+            diagnostics.extend(parsed.diagnostics);
+
+            let expected = *expected.get_or_insert(parsed.lists.map(|(_, len)| len));
+            for (list, ((column, len), expected)) in [List::Winning, List::Have]
+                .into_iter()
+                .zip(parsed.lists.into_iter().zip(expected))
+            {
+                if len != expected {
+                    diagnostics.push(Diagnostic {
+                        line: row + 1,
+                        column,
+                        problem: Problem::Length {
+                            list,
+                            len,
+                            expected,
+                        },
+                    });
+                }
+            }
+
+            cards.push(parsed.card);
+        }
+
+        if fatal || (mode == Mode::Strict && !diagnostics.is_empty()) {
+            return Err(diagnostics);
+        }
+
+        Ok((Self { cards }, diagnostics))
+    }
+}
+
+impl State {
+    /// Parses in `mode`, logging the problems it tolerated as warnings. Any
+    /// problem it could not tolerate fails the parse with every diagnostic.
+    fn parse_logged<S: AsRef<str>>(
+        lines: impl IntoIterator<Item = S>,
+        mode: Mode,
+    ) -> Result<Self, Error> {
+        match Self::parse(lines, mode) {
+            Ok((state, warnings)) => {
+                for warning in warnings {
+                    aoc::warn!("{warning}");
+                }
+                Ok(state)
+            }
+            Err(errors) => Err(Error::Parse(join(&errors))),
+        }
+    }
+}
+
 impl<D: AsRef<str>> std::iter::FromIterator<D> for State {
     fn from_iter<T: IntoIterator<Item = D>>(iter: T) -> Self {
-        Self {
-            cards: iter
-                .into_iter()
-                .map(|s| s.as_ref().parse())
-                .collect::<Result<_, _>>()
-                .unwrap(),
+        Self::parse_logged(iter, Mode::Lenient).unwrap_or_else(|err| panic!("{err}"))
+    }
+}
+
+fn join(diagnostics: &[Diagnostic]) -> String {
+    diagnostics
+        .iter()
+        .map(Diagnostic::to_string)
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Mode {
+    Lenient,
+    Strict,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum List {
+    Winning,
+    Have,
+}
+
+impl std::fmt::Display for List {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            List::Winning => f.write_str("winning numbers"),
+            List::Have => f.write_str("numbers"),
         }
     }
 }
 
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Problem {
+    Header,
+    MissingSeparator,
+    MalformedNumber(String),
+    DuplicateWinning(i32),
+    /// A list with a different length than on the first card.
+    Length {
+        list: List,
+        len: usize,
+        expected: usize,
+    },
+}
+
+impl std::fmt::Display for Problem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Problem::Header => f.write_str("expected `Card N:`"),
+            Problem::MissingSeparator => f.write_str("missing `|` before the numbers"),
+            Problem::MalformedNumber(token) => write!(f, "malformed number `{token}`"),
+            Problem::DuplicateWinning(n) => write!(f, "duplicate winning number {n}"),
+            Problem::Length {
+                list,
+                len,
+                expected,
+            } => write!(f, "{len} {list}, expected {expected} as on the first card"),
+        }
+    }
+}
+
+/// A problem with the input, at a 1-based line and column.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Diagnostic {
+    line: usize,
+    column: usize,
+    problem: Problem,
+}
+
+impl std::fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "line {}, column {}: {}",
+            self.line, self.column, self.problem
+        )
+    }
+}
+
 #[derive(Debug)]
 struct Card {
     idx: usize,
@@ -24,88 +171,155 @@ struct Card {
     numbers: Vec<i32>,
 }
 
-impl std::str::FromStr for Card {
-    type Err = ();
+/// A card with the problems found in its line.
+struct Parsed {
+    card: Card,
+    diagnostics: Vec<Diagnostic>,
+    /// The column and number of tokens of the winning numbers and the
+    /// numbers.
+    lists: [(usize, usize); 2],
+}
+
+/// The space separated tokens of `s` with their byte offset, counting from
+/// `offset`.
+fn tokens(s: &str, offset: usize) -> impl Iterator<Item = (usize, &str)> {
+    s.split(' ')
+        .scan(offset, |pos, token| {
+            let start = *pos;
+            *pos += token.len() + 1;
+            Some((start, token.trim()))
+        })
+        .filter(|(_, token)| !token.is_empty())
+}
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let s = s.strip_prefix("Card ").ok_or(())?;
+impl Card {
+    /// Parses the card on line `row`. Malformed numbers are skipped and
+    /// duplicate winning numbers merged, each with a diagnostic.
+    fn parse(line: &str, row: usize) -> Result<Parsed, Diagnostic> {
+        let at = |column: usize, problem| Diagnostic {
+            line: row + 1,
+            column: column + 1,
+            problem,
+        };
 
-        let mut splits = s.splitn(2, ':');
+        let (header, lists) = line.split_once(':').ok_or_else(|| at(0, Problem::Header))?;
+        let idx = header
+            .strip_prefix("Card ")
+            .and_then(|idx| idx.trim().parse::<usize>().ok())
+            .ok_or_else(|| at(0, Problem::Header))?;
 
-        let idx = splits
-            .next()
-            .ok_or(())?
-            .trim()
-            .parse::<usize>()
-            .map_err(|_| ())?;
-        let mut numbers = splits
-            .next()
-            .ok_or(())?
-            .split('|')
-            .map(|s| s.split(' ').filter_map(|s| s.trim().parse::<i32>().ok()));
+        let start = header.len() + 1;
+        let (winning, have) = lists
+            .split_once('|')
+            .ok_or_else(|| at(line.len(), Problem::MissingSeparator))?;
 
-        let winning_numbers = numbers.next().ok_or(())?.collect::<HashSet<i32>>();
-        let numbers = numbers.next().ok_or(())?.collect::<Vec<i32>>();
+        let mut diagnostics = Vec::new();
+        let mut parse = |list: &str, offset: usize| {
+            let tokens = tokens(list, offset).collect::<Vec<_>>();
+            let numbers = tokens
+                .iter()
+                .filter_map(|(column, token)| match token.parse::<i32>() {
+                    Ok(n) => Some((*column, n)),
+                    Err(_) => {
+                        diagnostics.push(at(*column, Problem::MalformedNumber(token.to_string())));
+                        None
+                    }
+                })
+                .collect::<Vec<_>>();
+            let column = tokens.first().map_or(offset, |(column, _)| *column);
+            (numbers, (column + 1, tokens.len()))
+        };
 
-        Ok(Self {
-            idx,
-            winning_numbers,
-            numbers,
+        let have_start = start + winning.len() + 1;
+        let (winning, winning_list) = parse(winning, start);
+        let (numbers, have_list) = parse(have, have_start);
+
+        let mut winning_numbers = HashSet::new();
+        for (column, n) in winning {
+            if !winning_numbers.insert(n) {
+                diagnostics.push(at(column, Problem::DuplicateWinning(n)));
+            }
+        }
+        diagnostics.sort_by_key(|diagnostic| diagnostic.column);
+
+        Ok(Parsed {
+            card: Self {
+                idx,
+                winning_numbers,
+                numbers: numbers.into_iter().map(|(_, n)| n).collect(),
+            },
+            diagnostics,
+            lists: [winning_list, have_list],
         })
     }
 }
 
-fn part_1(state: &State) -> i32 {
-    state
-        .cards
-        .iter()
-        .map(|card| {
+fn part_1(state: &State) -> Result<i32, Error> {
+    checked::try_sum(
+        DAY,
+        state.cards.iter().map(|card| {
             let count = card
                 .numbers
                 .iter()
                 .filter(|n| card.winning_numbers.contains(n))
                 .count() as u32;
+            aoc::debug!("card {}: {count} matching numbers", card.idx);
+
             if count == 0 {
-                0
+                Ok(0)
             } else {
-                2_i32.pow(count - 1)
+                Ok(checked::pow(DAY, 2_i32, count - 1)?)
             }
-        })
-        .sum()
+        }),
+    )
 }
 
-fn part_2(state: &State) -> i32 {
-    state
+fn part_2(state: &State) -> Result<i32, Error> {
+    let copies = state
         .cards
         .iter()
-        .fold(vec![1; state.cards.len()], |mut acc, card| {
+        .try_fold(vec![1; state.cards.len()], |mut acc, card| {
+            aoc::checkpoint()?;
+
             let count = card
                 .numbers
                 .iter()
                 .filter(|n| card.winning_numbers.contains(n))
                 .count();
             for i in card.idx..card.idx + count {
-                acc[i] += acc[card.idx - 1];
+                acc[i] = checked::add(DAY, acc[i], acc[card.idx - 1])?;
             }
 
-            acc
-        })
-        .iter()
-        .sum()
-}
+            Ok::<_, Error>(acc)
+        })?;
 
-fn main() {
-    let state = aoc::get_input();
+    Ok(checked::sum(DAY, copies)?)
+}
 
-    println!("part 1: {}", part_1(&state));
+fn main() -> std::process::ExitCode {
+    let mut args = aoc::Args::from_env();
+    let mode = if args.flag("strict") {
+        Mode::Strict
+    } else {
+        Mode::Lenient
+    };
 
-    println!("part 2: {}", part_2(&state));
+    aoc::runner::run_parsed(
+        DAY,
+        args,
+        move |lines| State::parse_logged(lines, mode),
+        part_1,
+        part_2,
+    )
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[cfg(feature = "checked")]
+    use aoc::Overflow;
+
     const INPUT: &str = include_str!("../inputs/day4");
     const EXAMPLE_INPUT: &str = include_str!("../examples/day4");
 
@@ -113,27 +327,101 @@ mod tests {
     fn test_example_part_1() {
         let cards = aoc::get_input_from(EXAMPLE_INPUT);
 
-        assert_eq!(part_1(&cards), 13);
+        assert_eq!(part_1(&cards), Ok(13));
     }
 
     #[test]
     fn test_example_part_2() {
         let cards = aoc::get_input_from(EXAMPLE_INPUT);
 
-        assert_eq!(part_2(&cards), 30);
+        assert_eq!(part_2(&cards), Ok(30));
+    }
+
+    #[test]
+    fn test_diagnostics() {
+        let input = "Card 1: 41 48 83 | 83 86  6\n\
+                     Card 2: 13 4O 13 | 61 30";
+
+        let (state, warnings) = State::parse(input.lines(), Mode::Lenient).unwrap();
+        assert_eq!(part_1(&state), Ok(1));
+        assert_eq!(
+            warnings
+                .iter()
+                .map(Diagnostic::to_string)
+                .collect::<Vec<_>>(),
+            [
+                "line 2, column 12: malformed number `4O`",
+                "line 2, column 15: duplicate winning number 13",
+                "line 2, column 20: 2 numbers, expected 3 as on the first card",
+            ]
+        );
+        assert_eq!(
+            State::parse(input.lines(), Mode::Strict).unwrap_err(),
+            warnings
+        );
+
+        let errors = State::parse(["Card 1: 1 2 3", "Card 2 1 | 2"], Mode::Lenient).unwrap_err();
+        assert_eq!(
+            errors.iter().map(Diagnostic::to_string).collect::<Vec<_>>(),
+            [
+                "line 1, column 14: missing `|` before the numbers",
+                "line 2, column 1: expected `Card N:`",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_logged() {
+        let input = "Card 1: 41 48 83 | 83 86  6\n\
+                     Card 2: 13 4O 13 | 61 30";
+
+        assert_eq!(
+            State::parse_logged(input.lines(), Mode::Strict).map(|_| ()),
+            Err(Error::Parse(
+                "line 2, column 12: malformed number `4O`\n\
+                 line 2, column 15: duplicate winning number 13\n\
+                 line 2, column 20: 2 numbers, expected 3 as on the first card"
+                    .to_owned()
+            ))
+        );
+
+        let state = State::parse_logged(input.lines(), Mode::Lenient).unwrap();
+        assert_eq!(part_1(&state), Ok(1));
+        assert_eq!(part_2(&state), Ok(3));
+    }
+
+    #[cfg(feature = "checked")]
+    #[test]
+    fn test_overflow() {
+        let numbers = (1..=40)
+            .map(|n| n.to_string())
+            .collect::<Vec<_>>()
+            .join(" ");
+        let line = format!("Card 1: {numbers} | {numbers}");
+        let cards = aoc::get_input_from(&line);
+
+        assert_eq!(
+            part_1(&cards),
+            Err(Error::Overflow(Overflow {
+                day: DAY,
+                op: checked::Op::Pow,
+                lhs: 2,
+                rhs: 39,
+            }))
+        );
     }
 
     #[test]
     fn test_part_1() {
         let cards = aoc::get_input_from(INPUT);
 
-        assert_eq!(part_1(&cards), 24733);
+        assert_eq!(part_1(&cards), Ok(24733));
     }
 
     #[test]
     fn test_part_2() {
         let cards = aoc::get_input_from(INPUT);
 
-        assert_eq!(part_2(&cards), 5422730);
+        assert_eq!(part_2(&cards), Ok(5422730));
     }
 }
@@ -1,4 +1,8 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+
+use aoc::{checked, Error};
+
+const DAY: u8 = 5;
 
 #[derive(Debug, Default)]
 struct State {
@@ -8,91 +12,106 @@ struct State {
 
 impl<D: AsRef<str>> std::iter::FromIterator<D> for State {
     fn from_iter<T: IntoIterator<Item = D>>(iter: T) -> Self {
-        let lines = iter.into_iter().collect::<Vec<_>>();
-
-        let empty_lines =
-            std::iter::once(0).chain(lines.iter().enumerate().filter_map(|(idx, line)| {
-                if line.as_ref().is_empty() {
-                    Some(idx)
-                } else {
-                    None
-                }
-            }));
-
-        let next_idx = empty_lines
-            .clone()
-            .skip(1)
-            .chain(std::iter::once(lines.len()));
+        Self::parse(&iter.into_iter().collect::<Vec<_>>()).unwrap_or_else(|err| panic!("{err}"))
+    }
+}
+
+impl State {
+    fn parse<S: AsRef<str>>(lines: &[S]) -> Result<Self, Error> {
+        let invalid = |idx: usize, what: String| Error::Parse(format!("line {}: {what}", idx + 1));
+
+        let mut blocks = Vec::new();
+        let mut start = 0;
+        for (idx, line) in lines.iter().enumerate() {
+            if line.as_ref().is_empty() {
+                blocks.push((start, &lines[start..idx]));
+                start = idx + 1;
+            }
+        }
+        blocks.push((start, &lines[start..]));
 
+        let mut seeds = Vec::new();
         let mut conversion_map = HashMap::new();
 
-        let seeds = empty_lines
-            .zip(next_idx)
-            .map(|(idx, next_idx)| {
-                if idx == 0 {
-                    lines[idx..next_idx].iter()
-                } else {
-                    lines[idx + 1..next_idx].iter()
-                }
-            })
-            .fold(Vec::new(), |mut state, mut lines| {
-                let header = lines.next().unwrap().as_ref();
-
-                if let Some(seeds) = header.strip_prefix("seeds: ") {
-                    state = seeds.split(' ').filter_map(|s| s.parse().ok()).collect();
-                } else if let Some(conversions) = header.strip_suffix(" map:") {
-                    let mut from_to = conversions.split('-');
-                    let from = from_to.next().unwrap();
-                    from_to.next().unwrap();
-                    let to = from_to.next().unwrap();
-
-                    let mut mappings = lines
-                        .map(|line| {
-                            let mut ranges = line
-                                .as_ref()
-                                .split(' ')
-                                .filter_map(|s| s.parse::<i64>().ok());
-                            let dest_start = ranges.next().unwrap();
-                            let src_start = ranges.next().unwrap();
-                            let length = ranges.next().unwrap();
-
-                            (src_start..src_start + length, dest_start)
-                        })
-                        .collect::<Vec<_>>();
+        for (start, block) in blocks {
+            let Some((header, lines)) = block.split_first() else {
+                continue;
+            };
+            let header = header.as_ref();
+
+            if let Some(list) = header.strip_prefix("seeds: ") {
+                seeds = list.split(' ').filter_map(|s| s.parse().ok()).collect();
+            } else if let Some(conversions) = header.strip_suffix(" map:") {
+                let (from, to) = conversions
+                    .split_once("-to-")
+                    .ok_or_else(|| invalid(start, format!("invalid map `{conversions}`")))?;
+
+                let mut mappings = lines
+                    .iter()
+                    .enumerate()
+                    .map(|(offset, line)| {
+                        let ranges = line
+                            .as_ref()
+                            .split(' ')
+                            .filter_map(|s| s.parse::<i64>().ok())
+                            .collect::<Vec<_>>();
+                        let [dest_start, src_start, length] = ranges[..] else {
+                            return Err(invalid(
+                                start + 1 + offset,
+                                "expected 3 numbers".to_string(),
+                            ));
+                        };
 
-                    mappings.sort_by_key(|(src_start, _)| src_start.start);
+                        let src_end = checked::add(DAY, src_start, length)?;
 
-                    conversion_map.insert(from, (to, mappings));
-                } else {
-                    panic!("invalid header: {}", header);
-                }
+                        Ok((src_start..src_end, dest_start))
+                    })
+                    .collect::<Result<Vec<_>, Error>>()?;
 
-                state
-            });
+                mappings.sort_by_key(|(src_start, _)| src_start.start);
+
+                conversion_map.insert(from, (to, mappings));
+            } else {
+                return Err(invalid(start, format!("invalid header `{header}`")));
+            }
+        }
 
         let mut conversions = Vec::with_capacity(conversion_map.len());
 
+        let mut visited = HashSet::new();
         let mut current = START;
         while current != END {
-            let (next, mappings) = conversion_map.remove(current).unwrap();
+            aoc::checkpoint()?;
+
+            if !visited.insert(current) {
+                return Err(Error::Parse(format!(
+                    "maps form a cycle through `{current}`"
+                )));
+            }
+
+            let (next, mappings) = conversion_map
+                .remove(current)
+                .ok_or_else(|| Error::Parse(format!("no map from `{current}`")))?;
             conversions.push(mappings);
             current = next;
         }
 
-        Self { seeds, conversions }
+        Ok(Self { seeds, conversions })
     }
 }
 
 const START: &str = "seed";
 const END: &str = "location";
 
-fn part_1(state: &State) -> i64 {
-    state
+fn part_1(state: &State) -> Result<i64, Error> {
+    let locations = state
         .seeds
         .iter()
         .copied()
         .map(|seed| {
-            state.conversions.iter().fold(seed, |seed, mappings| {
+            aoc::checkpoint()?;
+
+            let location = state.conversions.iter().try_fold(seed, |seed, mappings| {
                 mappings
                     .binary_search_by(|(range, _)| {
                         if range.contains(&seed) {
@@ -107,96 +126,117 @@ fn part_1(state: &State) -> i64 {
                     .map(|idx| {
                         let (range, dest) = &mappings[idx];
 
-                        dest + seed - range.start
+                        checked::sub(DAY, checked::add(DAY, *dest, seed)?, range.start)
                     })
-                    .unwrap_or(seed)
-            })
+                    .unwrap_or(Ok(seed))
+            })?;
+
+            Ok(location)
         })
-        .min()
-        .unwrap()
+        .collect::<Result<Vec<_>, Error>>()?;
+
+    Ok(locations.into_iter().min().unwrap())
 }
 
-fn part_2(state: &State) -> i64 {
-    state
+fn part_2(state: &State) -> Result<i64, Error> {
+    let seeds = state
+        .seeds
+        .chunks(2)
+        .map(|seeds| Ok(seeds[0]..checked::add(DAY, seeds[0], seeds[1])?))
+        .collect::<Result<Vec<_>, Error>>()?;
+
+    let locations = state
         .conversions
         .iter()
-        .fold(
-            state
-                .seeds
-                .chunks(2)
-                .map(|seeds| seeds[0]..seeds[0] + seeds[1])
-                .collect::<Vec<_>>(),
-            |mut seeds, mappings| {
-                let new_ranges = mappings
-                    .iter()
-                    .flat_map(|(mapped_range, dest)| {
-                        let addition = dest - mapped_range.start;
-
-                        let mut old_seeds = Vec::new();
-
-                        let new_seeds = seeds
-                            .iter_mut()
-                            .filter_map(|range| {
-                                let range_start = range.start;
-                                let range_end = range.end;
-
-                                match (
-                                    mapped_range.contains(&range.start),
-                                    mapped_range.contains(&range.end),
-                                    range.contains(&mapped_range.start),
-                                    range.contains(&mapped_range.end),
-                                ) {
-                                    (true, true, _, _) => {
-                                        range.start = 0;
-                                        range.end = 0;
-                                        Some(range_start + addition..range_end + addition)
-                                    }
-                                    (true, false, _, _) => {
-                                        range.start = mapped_range.end;
-                                        Some(range_start + addition..mapped_range.end + addition)
-                                    }
-                                    (false, true, _, _) => {
-                                        range.end = mapped_range.start;
-                                        Some(mapped_range.start + addition..range_end + addition)
-                                    }
-                                    (false, false, true, true) => {
-                                        range.end = mapped_range.start;
-                                        old_seeds.push(mapped_range.end..range_end);
-                                        Some(mapped_range.start + addition
-                                            ..mapped_range.end + addition)
-                                    }
-                                    (false, false, false, false) => None,
-                                    _ => unreachable!(
-                                        "invalid range: range={range:?} mapped_range={mapped_range:?}"
-                                    ),
+        .try_fold(seeds, |mut seeds, mappings| {
+            aoc::checkpoint()?;
+
+            let new_ranges = mappings
+                .iter()
+                .map(|(mapped_range, dest)| {
+                    let addition = checked::sub(DAY, *dest, mapped_range.start)?;
+                    let shift = |range: std::ops::Range<i64>| {
+                        Ok(checked::add(DAY, range.start, addition)?
+                            ..checked::add(DAY, range.end, addition)?)
+                    };
+
+                    let mut old_seeds = Vec::new();
+
+                    let new_seeds = seeds
+                        .iter_mut()
+                        .filter_map(|range| {
+                            let range_start = range.start;
+                            let range_end = range.end;
+
+                            match (
+                                mapped_range.contains(&range.start),
+                                mapped_range.contains(&range.end),
+                                range.contains(&mapped_range.start),
+                                range.contains(&mapped_range.end),
+                            ) {
+                                (true, true, _, _) => {
+                                    aoc::debug!(
+                                        "{range_start}..{range_end} inside {mapped_range:?}"
+                                    );
+                                    range.start = 0;
+                                    range.end = 0;
+                                    Some(shift(range_start..range_end))
                                 }
-                            })
-                            .collect::<Vec<_>>();
-
-                        seeds.retain(|r| r.start != r.end);
-                        seeds.extend(old_seeds);
+                                (true, false, _, _) => {
+                                    aoc::debug!(
+                                        "split {range_start}..{range_end} at {}",
+                                        mapped_range.end
+                                    );
+                                    range.start = mapped_range.end;
+                                    Some(shift(range_start..mapped_range.end))
+                                }
+                                (false, true, _, _) => {
+                                    aoc::debug!(
+                                        "split {range_start}..{range_end} at {}",
+                                        mapped_range.start
+                                    );
+                                    range.end = mapped_range.start;
+                                    Some(shift(mapped_range.start..range_end))
+                                }
+                                (false, false, true, true) => {
+                                    aoc::debug!(
+                                        "split {range_start}..{range_end} around {mapped_range:?}"
+                                    );
+                                    range.end = mapped_range.start;
+                                    old_seeds.push(mapped_range.end..range_end);
+                                    Some(shift(mapped_range.clone()))
+                                }
+                                (false, false, false, false) => None,
+                                _ => unreachable!(
+                                    "invalid range: range={range:?} mapped_range={mapped_range:?}"
+                                ),
+                            }
+                        })
+                        .collect::<Result<Vec<_>, _>>();
 
-                        new_seeds
-                    })
-                    .collect::<Vec<_>>();
+                    seeds.retain(|r| r.start != r.end);
+                    seeds.extend(old_seeds);
 
-                seeds.extend(new_ranges);
+                    new_seeds
+                })
+                .collect::<Result<Vec<_>, Error>>()?;
 
-                seeds
-            },
-        )
-        .into_iter()
-        .map(|r| r.start)
-        .min()
-        .unwrap()
-}
+            seeds.extend(new_ranges.into_iter().flatten());
 
-fn main() {
-    let state = aoc::get_input();
+            Ok::<_, Error>(seeds)
+        })?;
 
-    println!("part 1: {}", part_1(&state));
+    Ok(locations.into_iter().map(|r| r.start).min().unwrap())
+}
 
-    println!("part 2: {}", part_2(&state));
+fn main() -> std::process::ExitCode {
+    aoc::runner::run_parsed(
+        DAY,
+        aoc::Args::from_env(),
+        |lines| State::parse(&lines),
+        part_1,
+        part_2,
+    )
 }
 
 #[cfg(test)]
@@ -210,27 +250,71 @@ mod tests {
     fn test_example_part_1() {
         let state = aoc::get_input_from(EXAMPLE_INPUT);
 
-        assert_eq!(part_1(&state), 35);
+        assert_eq!(part_1(&state), Ok(35));
     }
 
     #[test]
     fn test_example_part_2() {
         let state = aoc::get_input_from(EXAMPLE_INPUT);
 
-        assert_eq!(part_2(&state), 46);
+        assert_eq!(part_2(&state), Ok(46));
+    }
+
+    #[cfg(feature = "checked")]
+    #[test]
+    fn test_overflow() {
+        let input = [
+            "seeds: 1",
+            "",
+            "seed-to-location map:",
+            "0 9223372036854775807 1",
+        ];
+
+        assert_eq!(
+            State::parse(&input).unwrap_err(),
+            Error::Overflow(aoc::Overflow {
+                day: DAY,
+                op: checked::Op::Add,
+                lhs: i64::MAX.into(),
+                rhs: 1,
+            })
+        );
+    }
+
+    #[test]
+    fn test_broken_chain() {
+        let missing = ["seeds: 1", "", "seed-to-soil map:", "0 1 1"];
+        assert_eq!(
+            State::parse(&missing).unwrap_err(),
+            Error::Parse("no map from `soil`".to_string())
+        );
+
+        let cycle = [
+            "seeds: 1",
+            "",
+            "seed-to-soil map:",
+            "0 1 1",
+            "",
+            "soil-to-seed map:",
+            "0 1 1",
+        ];
+        assert_eq!(
+            State::parse(&cycle).unwrap_err(),
+            Error::Parse("maps form a cycle through `seed`".to_string())
+        );
     }
 
     #[test]
     fn test_part_1() {
         let state = aoc::get_input_from(INPUT);
 
-        assert_eq!(part_1(&state), 174137457);
+        assert_eq!(part_1(&state), Ok(174137457));
     }
 
     #[test]
     fn test_part_2() {
         let state = aoc::get_input_from(INPUT);
 
-        assert_eq!(part_2(&state), 1493866);
+        assert_eq!(part_2(&state), Ok(1493866));
     }
 }
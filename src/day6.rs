@@ -1,3 +1,7 @@
+use aoc::{checked, Error};
+
+const DAY: u8 = 6;
+
 #[derive(Debug)]
 struct Race {
     time: i64,
@@ -11,41 +15,49 @@ struct State {
 
 impl<D: AsRef<str>> std::iter::FromIterator<D> for State {
     fn from_iter<T: IntoIterator<Item = D>>(iter: T) -> Self {
-        let mut iter = iter.into_iter();
-
-        let time = iter
-            .next()
-            .unwrap()
-            .as_ref()
-            .strip_prefix("Time:")
-            .unwrap()
-            .trim()
-            .split(' ')
-            .filter_map(|s| s.parse().ok())
-            .collect::<Vec<_>>();
-        let distance = iter
-            .next()
-            .unwrap()
-            .as_ref()
-            .strip_prefix("Distance:")
-            .unwrap()
-            .trim()
-            .split(' ')
-            .filter_map(|s| s.parse().ok())
-            .collect::<Vec<_>>();
-
-        Self {
+        Self::parse(iter).unwrap_or_else(|err| panic!("{err}"))
+    }
+}
+
+impl State {
+    fn parse<S: AsRef<str>>(lines: impl IntoIterator<Item = S>) -> Result<Self, Error> {
+        let mut lines = lines.into_iter();
+        let mut numbers = |idx: usize, prefix: &str| {
+            let invalid = |what: String| Error::Parse(format!("line {}: {what}", idx + 1));
+
+            let line = lines
+                .next()
+                .ok_or_else(|| invalid(format!("expected `{prefix}`")))?;
+            line.as_ref()
+                .strip_prefix(prefix)
+                .ok_or_else(|| invalid(format!("expected `{prefix}`")))?
+                .split_whitespace()
+                .map(|s| {
+                    s.parse::<i64>()
+                        .map_err(|_| invalid(format!("invalid number `{s}`")))
+                })
+                .collect::<Result<Vec<_>, _>>()
+        };
+
+        let time = numbers(0, "Time:")?;
+        let distance = numbers(1, "Distance:")?;
+
+        Ok(Self {
             races: time
                 .into_iter()
                 .zip(distance)
                 .map(|(time, distance)| Race { time, distance })
                 .collect::<Vec<_>>(),
-        }
+        })
     }
 }
 
-fn find_root_distance(time: i64, distance: i64) -> i64 {
-    let discriminant = (time * time) - 4 * distance;
+fn find_root_distance(time: i64, distance: i64) -> Result<i64, Error> {
+    let discriminant = checked::sub(
+        DAY,
+        checked::mul(DAY, time, time)?,
+        checked::mul(DAY, 4, distance)?,
+    )?;
     assert!(discriminant >= 0, "discriminant is negative");
 
     let sqrt = (discriminant as f64).sqrt();
@@ -53,37 +65,48 @@ fn find_root_distance(time: i64, distance: i64) -> i64 {
     let root_1 = ((time as f64 - sqrt) / 2.0 + 1.0).floor() as i64;
     let root_2 = ((time as f64 + sqrt) / 2.0 - 1.0).ceil() as i64;
 
-    root_2 - root_1 + 1
-}
+    aoc::debug!("time {time}, distance {distance}: wins from {root_1} to {root_2}");
 
-fn part_1(state: &State) -> i64 {
-    state
-        .races
-        .iter()
-        .map(|race| find_root_distance(race.time, race.distance))
-        .product::<i64>()
+    Ok(checked::add(DAY, checked::sub(DAY, root_2, root_1)?, 1)?)
 }
 
-fn part_2(state: &State) -> i64 {
-    let (time, distance) = state.races.iter().fold((0, 0), |(time, distance), race| {
-        let time_log_10 = (race.time as f64).log10().ceil() as u32;
-        let distance_log_10 = (race.distance as f64).log10().ceil() as u32;
-
-        let time = time * 10i64.pow(time_log_10) + race.time;
-        let distance = distance * 10i64.pow(distance_log_10) + race.distance;
+fn part_1(state: &State) -> Result<i64, Error> {
+    checked::try_product(
+        DAY,
+        state
+            .races
+            .iter()
+            .map(|race| find_root_distance(race.time, race.distance)),
+    )
+}
 
-        (time, distance)
-    });
+fn part_2(state: &State) -> Result<i64, Error> {
+    let (time, distance) = state
+        .races
+        .iter()
+        .try_fold((0, 0), |(time, distance), race| {
+            let time_log_10 = (race.time as f64).log10().ceil() as u32;
+            let distance_log_10 = (race.distance as f64).log10().ceil() as u32;
+
+            let time = checked::add(
+                DAY,
+                checked::mul(DAY, time, checked::pow(DAY, 10i64, time_log_10)?)?,
+                race.time,
+            )?;
+            let distance = checked::add(
+                DAY,
+                checked::mul(DAY, distance, checked::pow(DAY, 10i64, distance_log_10)?)?,
+                race.distance,
+            )?;
+
+            Ok::<_, Error>((time, distance))
+        })?;
 
     find_root_distance(time, distance)
 }
 
-fn main() {
-    let state = aoc::get_input();
-
-    println!("part 1: {}", part_1(&state));
-
-    println!("part 2: {}", part_2(&state));
+fn main() -> std::process::ExitCode {
+    aoc::runner::run_parsed(DAY, aoc::Args::from_env(), State::parse, part_1, part_2)
 }
 
 #[cfg(test)]
@@ -97,27 +120,55 @@ mod tests {
     fn test_example_part_1() {
         let state = aoc::get_input_from(EXAMPLE_INPUT);
 
-        assert_eq!(part_1(&state), 288);
+        assert_eq!(part_1(&state), Ok(288));
     }
 
     #[test]
     fn test_example_part_2() {
         let state = aoc::get_input_from(EXAMPLE_INPUT);
 
-        assert_eq!(part_2(&state), 71503);
+        assert_eq!(part_2(&state), Ok(71503));
+    }
+
+    #[cfg(feature = "checked")]
+    #[test]
+    fn test_overflow() {
+        let state = aoc::get_input_from("Time: 9223372036854775807\nDistance: 1");
+
+        assert_eq!(
+            part_2(&state),
+            Err(Error::Overflow(aoc::Overflow {
+                day: DAY,
+                op: checked::Op::Pow,
+                lhs: 10,
+                rhs: 19,
+            }))
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(
+            State::parse(["Time: 7 15"]).unwrap_err(),
+            Error::Parse("line 2: expected `Distance:`".to_string())
+        );
+        assert_eq!(
+            State::parse(["Time: 7 1S", "Distance: 9 40"]).unwrap_err(),
+            Error::Parse("line 1: invalid number `1S`".to_string())
+        );
     }
 
     #[test]
     fn test_part_1() {
         let state = aoc::get_input_from(INPUT);
 
-        assert_eq!(part_1(&state), 114400);
+        assert_eq!(part_1(&state), Ok(114400));
     }
 
     #[test]
     fn test_part_2() {
         let state = aoc::get_input_from(INPUT);
 
-        assert_eq!(part_2(&state), 21039729);
+        assert_eq!(part_2(&state), Ok(21039729));
     }
 }
@@ -1,3 +1,7 @@
+use aoc::{checked, Error};
+
+const DAY: u8 = 7;
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 enum Card {
     A,
@@ -28,16 +32,27 @@ struct State {
 
 impl<D: AsRef<str>> std::iter::FromIterator<D> for State {
     fn from_iter<T: IntoIterator<Item = D>>(iter: T) -> Self {
-        Self {
-            decks: iter
-                .into_iter()
-                .map(|line| {
-                    let mut splits = line.as_ref().split(' ');
-                    let cards = splits
-                        .next()
-                        .unwrap()
-                        .chars()
-                        .map(|c| match c {
+        Self::parse(iter).unwrap_or_else(|err| panic!("{err}"))
+    }
+}
+
+impl State {
+    fn parse<S: AsRef<str>>(lines: impl IntoIterator<Item = S>) -> Result<Self, Error> {
+        let decks = lines
+            .into_iter()
+            .enumerate()
+            .map(|(idx, line)| {
+                let invalid = |what: String| Error::Parse(format!("line {}: {what}", idx + 1));
+                let line = line.as_ref();
+
+                let (cards, bid) = line
+                    .split_once(' ')
+                    .ok_or_else(|| invalid(format!("expected `CARDS BID`, got `{line}`")))?;
+
+                let cards = cards
+                    .chars()
+                    .map(|c| {
+                        Ok(match c {
                             'A' => Card::A,
                             'K' => Card::K,
                             'Q' => Card::Q,
@@ -51,16 +66,23 @@ impl<D: AsRef<str>> std::iter::FromIterator<D> for State {
                             '4' => Card::N4,
                             '3' => Card::N3,
                             '2' => Card::N2,
-                            _ => unreachable!(),
+                            _ => return Err(invalid(format!("invalid card `{c}`"))),
                         })
-                        .collect();
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+                if cards.len() != 5 {
+                    return Err(invalid(format!("{} cards, expected 5", cards.len())));
+                }
 
-                    let bid = splits.next().unwrap().parse().unwrap();
+                let bid = bid
+                    .parse()
+                    .map_err(|_| invalid(format!("invalid bid `{bid}`")))?;
 
-                    Deck { cards, bid }
-                })
-                .collect::<Vec<_>>(),
-        }
+                Ok(Deck { cards, bid })
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+
+        Ok(Self { decks })
     }
 }
 
@@ -75,7 +97,7 @@ enum DeckType {
     HighCard,
 }
 
-fn part_1(state: &State) -> i64 {
+fn part_1(state: &State) -> Result<i64, Error> {
     let mut decks = state
         .decks
         .iter()
@@ -104,6 +126,8 @@ fn part_1(state: &State) -> i64 {
                 _ => unreachable!(),
             };
 
+            aoc::debug!("{:?} is {deck_type:?}", deck.cards);
+
             (deck_type, deck)
         })
         .collect::<Vec<_>>();
@@ -116,14 +140,15 @@ fn part_1(state: &State) -> i64 {
         }
     });
 
-    decks
-        .iter()
-        .enumerate()
-        .map(|(idx, (_, deck))| deck.bid * (idx as i64 + 1))
-        .sum::<i64>()
+    checked::try_sum(
+        DAY,
+        decks.iter().enumerate().map(|(idx, (_, deck))| {
+            checked::mul(DAY, deck.bid, idx as i64 + 1).map_err(Error::from)
+        }),
+    )
 }
 
-fn part_2(state: &State) -> i64 {
+fn part_2(state: &State) -> Result<i64, Error> {
     let mut decks = state
         .decks
         .iter()
@@ -168,6 +193,8 @@ fn part_2(state: &State) -> i64 {
                 _ => panic!("invalid deck: ({counted_counts:?}, {joker_count})"),
             };
 
+            aoc::debug!("{:?} is {deck_type:?}", deck.cards);
+
             (deck_type, deck)
         })
         .collect::<Vec<_>>();
@@ -194,19 +221,16 @@ fn part_2(state: &State) -> i64 {
         unreachable!()
     });
 
-    decks
-        .iter()
-        .enumerate()
-        .map(|(idx, (_, deck))| deck.bid * (idx as i64 + 1))
-        .sum::<i64>()
+    checked::try_sum(
+        DAY,
+        decks.iter().enumerate().map(|(idx, (_, deck))| {
+            checked::mul(DAY, deck.bid, idx as i64 + 1).map_err(Error::from)
+        }),
+    )
 }
 
-fn main() {
-    let state = aoc::get_input();
-
-    println!("part 1: {}", part_1(&state));
-
-    println!("part 2: {}", part_2(&state));
+fn main() -> std::process::ExitCode {
+    aoc::runner::run_parsed(DAY, aoc::Args::from_env(), State::parse, part_1, part_2)
 }
 
 #[cfg(test)]
@@ -216,31 +240,48 @@ mod tests {
     const INPUT: &str = include_str!("../inputs/day7");
     const EXAMPLE_INPUT: &str = include_str!("../examples/day7");
 
+    #[test]
+    fn test_parse_errors() {
+        let errors = ["32T3K", "32T3X 765", "32T3 765", "32T3K 7b5"]
+            .into_iter()
+            .map(|line| State::parse([line]).unwrap_err().to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            errors,
+            [
+                "line 1: expected `CARDS BID`, got `32T3K`",
+                "line 1: invalid card `X`",
+                "line 1: 4 cards, expected 5",
+                "line 1: invalid bid `7b5`",
+            ]
+        );
+    }
+
     #[test]
     fn test_example_part_1() {
         let state = aoc::get_input_from(EXAMPLE_INPUT);
 
-        assert_eq!(part_1(&state), 6440);
+        assert_eq!(part_1(&state), Ok(6440));
     }
 
     #[test]
     fn test_example_part_2() {
         let state = aoc::get_input_from(EXAMPLE_INPUT);
 
-        assert_eq!(part_2(&state), 5905);
+        assert_eq!(part_2(&state), Ok(5905));
     }
 
     #[test]
     fn test_part_1() {
         let state = aoc::get_input_from(INPUT);
 
-        assert_eq!(part_1(&state), 249483956);
+        assert_eq!(part_1(&state), Ok(249483956));
     }
 
     #[test]
     fn test_part_2() {
         let state = aoc::get_input_from(INPUT);
 
-        assert_eq!(part_2(&state), 252137472);
+        assert_eq!(part_2(&state), Ok(252137472));
     }
 }
@@ -0,0 +1,456 @@
+//! Dense 2D grids parsed from lines of text.
+
+/// A cell position, counted from the top left corner.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Pos {
+    pub row: usize,
+    pub col: usize,
+}
+
+impl Pos {
+    pub fn new(row: usize, col: usize) -> Self {
+        Self { row, col }
+    }
+
+    /// Moves by `(rows, cols)`, or `None` if that leaves the first quadrant.
+    pub fn offset(self, (rows, cols): (isize, isize)) -> Option<Self> {
+        Some(Self {
+            row: self.row.checked_add_signed(rows)?,
+            col: self.col.checked_add_signed(cols)?,
+        })
+    }
+}
+
+impl std::fmt::Display for Pos {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.row + 1, self.col + 1)
+    }
+}
+
+/// Parses the `row:col` form written by `Display`, counting from 1.
+impl std::str::FromStr for Pos {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("invalid position `{s}`, expected `row:col`");
+        let (row, col) = s.split_once(':').ok_or_else(invalid)?;
+        let parse = |n: &str| match n.trim().parse::<usize>() {
+            Ok(n) if n > 0 => Ok(n - 1),
+            _ => Err(invalid()),
+        };
+
+        Ok(Self::new(parse(row)?, parse(col)?))
+    }
+}
+
+/// Offsets of the 4 orthogonal neighbors.
+pub const ORTHOGONAL: [(isize, isize); 4] = [(-1, 0), (0, -1), (0, 1), (1, 0)];
+
+/// Offsets of the 8 orthogonal and diagonal neighbors, in reading order.
+pub const ADJACENT: [(isize, isize); 8] = [
+    (-1, -1),
+    (-1, 0),
+    (-1, 1),
+    (0, -1),
+    (0, 1),
+    (1, -1),
+    (1, 0),
+    (1, 1),
+];
+
+/// A horizontal run of cells sharing the same key, see [`Grid::runs`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Run<K> {
+    pub row: usize,
+    pub cols: std::ops::Range<usize>,
+    pub key: K,
+}
+
+impl<K> Run<K> {
+    pub fn positions(&self) -> impl Iterator<Item = Pos> + '_ {
+        self.cols.clone().map(|col| Pos::new(self.row, col))
+    }
+}
+
+/// A line that is not as wide as the first one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Ragged {
+    pub row: usize,
+    pub width: usize,
+    pub expected: usize,
+}
+
+impl std::fmt::Display for Ragged {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "line {} is {} cells wide, expected {}",
+            self.row + 1,
+            self.width,
+            self.expected
+        )
+    }
+}
+
+impl std::error::Error for Ragged {}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Grid<T> {
+    width: usize,
+    height: usize,
+    cells: Vec<T>,
+}
+
+impl<T> Grid<T> {
+    pub fn new(width: usize, height: usize, fill: T) -> Self
+    where
+        T: Clone,
+    {
+        Self {
+            width,
+            height,
+            cells: vec![fill; width * height],
+        }
+    }
+
+    /// Parses one row per line and one cell per char. All lines must be as
+    /// wide as the first.
+    pub fn from_lines<S: AsRef<str>>(
+        lines: impl IntoIterator<Item = S>,
+        mut cell: impl FnMut(char) -> T,
+    ) -> Result<Self, Ragged> {
+        let mut grid = Self {
+            width: 0,
+            height: 0,
+            cells: Vec::new(),
+        };
+
+        for line in lines {
+            let len = grid.cells.len();
+            grid.cells.extend(line.as_ref().chars().map(&mut cell));
+
+            let width = grid.cells.len() - len;
+            if grid.height == 0 {
+                grid.width = width;
+            } else if width != grid.width {
+                return Err(Ragged {
+                    row: grid.height,
+                    width,
+                    expected: grid.width,
+                });
+            }
+            grid.height += 1;
+        }
+
+        Ok(grid)
+    }
+
+    /// Like [`Grid::from_lines`], but accepts lines of any width, padding
+    /// the short ones with `fill` to the width of the longest.
+    pub fn from_lines_padded<S: AsRef<str>>(
+        lines: impl IntoIterator<Item = S>,
+        fill: T,
+        mut cell: impl FnMut(char) -> T,
+    ) -> Self
+    where
+        T: Clone,
+    {
+        let rows = lines
+            .into_iter()
+            .map(|line| line.as_ref().chars().map(&mut cell).collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+
+        let width = rows.iter().map(Vec::len).max().unwrap_or(0);
+        let mut grid = Self::new(width, rows.len(), fill);
+        for (row, cells) in rows.into_iter().enumerate() {
+            for (col, cell) in cells.into_iter().enumerate() {
+                grid[Pos::new(row, col)] = cell;
+            }
+        }
+
+        grid
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn contains(&self, pos: Pos) -> bool {
+        pos.row < self.height && pos.col < self.width
+    }
+
+    pub fn get(&self, pos: Pos) -> Option<&T> {
+        self.contains(pos)
+            .then(|| &self.cells[pos.row * self.width + pos.col])
+    }
+
+    pub fn get_mut(&mut self, pos: Pos) -> Option<&mut T> {
+        self.contains(pos)
+            .then(|| &mut self.cells[pos.row * self.width + pos.col])
+    }
+
+    /// Applies `f` to every cell.
+    pub fn map<U>(&self, f: impl FnMut(&T) -> U) -> Grid<U> {
+        Grid {
+            width: self.width,
+            height: self.height,
+            cells: self.cells.iter().map(f).collect(),
+        }
+    }
+
+    pub fn positions(&self) -> impl Iterator<Item = Pos> {
+        let width = self.width;
+        (0..self.height).flat_map(move |row| (0..width).map(move |col| Pos::new(row, col)))
+    }
+
+    /// Every cell with its position, in reading order.
+    pub fn iter(&self) -> impl Iterator<Item = (Pos, &T)> {
+        self.positions().zip(&self.cells)
+    }
+
+    /// The positions reached from `pos` by each of `offsets` that stay inside
+    /// the grid.
+    pub fn neighbors<'a>(
+        &'a self,
+        pos: Pos,
+        offsets: &'a [(isize, isize)],
+    ) -> impl Iterator<Item = Pos> + 'a {
+        offsets
+            .iter()
+            .filter_map(move |offset| pos.offset(*offset))
+            .filter(|pos| self.contains(*pos))
+    }
+
+    /// The positions reached from `pos` by each of `offsets`, wrapping around
+    /// the edges as if the grid were a torus.
+    pub fn wrapping_neighbors<'a>(
+        &'a self,
+        pos: Pos,
+        offsets: &'a [(isize, isize)],
+    ) -> impl Iterator<Item = Pos> + 'a {
+        offsets.iter().map(move |(rows, cols)| {
+            Pos::new(
+                (pos.row as isize + rows).rem_euclid(self.height as isize) as usize,
+                (pos.col as isize + cols).rem_euclid(self.width as isize) as usize,
+            )
+        })
+    }
+
+    /// The up to 4 orthogonal neighbors of `pos`.
+    pub fn neighbors4(&self, pos: Pos) -> impl Iterator<Item = Pos> + '_ {
+        self.neighbors(pos, &ORTHOGONAL)
+    }
+
+    /// The up to 8 orthogonal and diagonal neighbors of `pos`.
+    pub fn neighbors8(&self, pos: Pos) -> impl Iterator<Item = Pos> + '_ {
+        self.neighbors(pos, &ADJACENT)
+    }
+
+    pub fn row(&self, row: usize) -> Option<&[T]> {
+        (row < self.height).then(|| &self.cells[row * self.width..(row + 1) * self.width])
+    }
+
+    pub fn rows(&self) -> impl Iterator<Item = &[T]> {
+        (0..self.height).map(|row| &self.cells[row * self.width..(row + 1) * self.width])
+    }
+
+    pub fn column(&self, col: usize) -> impl Iterator<Item = &T> {
+        self.line(Pos::new(0, col), (1, 0)).map(|(_, cell)| cell)
+    }
+
+    /// The cells from `start` on, moving by `step` until leaving the grid.
+    pub fn line(&self, start: Pos, step: (isize, isize)) -> impl Iterator<Item = (Pos, &T)> {
+        std::iter::successors(Some(start), move |pos| pos.offset(step))
+            .map_while(|pos| Some((pos, self.get(pos)?)))
+    }
+
+    /// Every diagonal running down and to the right, starting from the
+    /// bottom left corner.
+    pub fn diagonals(&self) -> impl Iterator<Item = impl Iterator<Item = (Pos, &T)>> {
+        let starts = (0..self.height)
+            .rev()
+            .map(|row| Pos::new(row, 0))
+            .chain((1..self.width).map(|col| Pos::new(0, col)));
+
+        starts.map(|start| self.line(start, (1, 1)))
+    }
+
+    /// Every diagonal running down and to the left, starting from the top
+    /// left corner.
+    pub fn anti_diagonals(&self) -> impl Iterator<Item = impl Iterator<Item = (Pos, &T)>> {
+        let last = self.width.saturating_sub(1);
+        let starts = (0..self.width)
+            .map(|col| Pos::new(0, col))
+            .chain((1..self.height).map(move |row| Pos::new(row, last)));
+
+        starts.map(|start| self.line(start, (1, -1)))
+    }
+
+    /// Groups each row into maximal runs of adjacent cells with the same
+    /// key, skipping cells whose key is `None`. With a key of "is a digit"
+    /// this splits a row like `467..114` into the numbers `467` and `114`.
+    pub fn runs<K: PartialEq>(
+        &self,
+        mut key: impl FnMut(&T) -> Option<K>,
+    ) -> impl Iterator<Item = Run<K>> {
+        let mut runs = Vec::new();
+
+        for (row, cells) in self.rows().enumerate() {
+            let mut current: Option<Run<K>> = None;
+            for (col, cell) in cells.iter().enumerate() {
+                let key = key(cell);
+                match (&mut current, key) {
+                    (Some(run), Some(key)) if run.key == key => run.cols.end = col + 1,
+                    (_, key) => {
+                        runs.extend(current.take());
+                        current = key.map(|key| Run {
+                            row,
+                            cols: col..col + 1,
+                            key,
+                        });
+                    }
+                }
+            }
+            runs.extend(current);
+        }
+
+        runs.into_iter()
+    }
+
+    /// The positions around a run of `cols` in `row`, reached by any of
+    /// `offsets` from a cell of the run, without the run itself. Each
+    /// position appears once, in reading order.
+    pub fn around(
+        &self,
+        row: usize,
+        cols: std::ops::Range<usize>,
+        offsets: &[(isize, isize)],
+    ) -> Vec<Pos> {
+        Self::collect_around(row, cols, |pos| self.neighbors(pos, offsets))
+    }
+
+    /// Like [`Grid::around`], but wrapping around the edges.
+    pub fn wrapping_around(
+        &self,
+        row: usize,
+        cols: std::ops::Range<usize>,
+        offsets: &[(isize, isize)],
+    ) -> Vec<Pos> {
+        Self::collect_around(row, cols, |pos| self.wrapping_neighbors(pos, offsets))
+    }
+
+    fn collect_around<I: Iterator<Item = Pos>>(
+        row: usize,
+        cols: std::ops::Range<usize>,
+        reach: impl FnMut(Pos) -> I,
+    ) -> Vec<Pos> {
+        let mut around = cols
+            .clone()
+            .map(|col| Pos::new(row, col))
+            .flat_map(reach)
+            .filter(|pos| pos.row != row || !cols.contains(&pos.col))
+            .collect::<Vec<_>>();
+        around.sort();
+        around.dedup();
+        around
+    }
+}
+
+impl<T> std::ops::Index<Pos> for Grid<T> {
+    type Output = T;
+
+    fn index(&self, pos: Pos) -> &T {
+        self.get(pos)
+            .unwrap_or_else(|| panic!("position {pos} is outside the grid"))
+    }
+}
+
+impl<T> std::ops::IndexMut<Pos> for Grid<T> {
+    fn index_mut(&mut self, pos: Pos) -> &mut T {
+        self.get_mut(pos)
+            .unwrap_or_else(|| panic!("position {pos} is outside the grid"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn grid() -> Grid<char> {
+        Grid::from_lines(["ab1", "c23", "..4"], |c| c).unwrap()
+    }
+
+    #[test]
+    fn test_access() {
+        let grid = grid();
+
+        assert_eq!((grid.width(), grid.height()), (3, 3));
+        assert_eq!(grid[Pos::new(1, 2)], '3');
+        assert_eq!(grid.get(Pos::new(3, 0)), None);
+        assert_eq!(grid.row(2), Some(&['.', '.', '4'][..]));
+        assert_eq!(grid.column(0).collect::<String>(), "ac.");
+        assert_eq!("2:3".parse(), Ok(Pos::new(1, 2)));
+        assert!("0:1".parse::<Pos>().is_err());
+
+        let err = Grid::from_lines(["ab", "c"], |c| c).unwrap_err();
+        assert_eq!(err.to_string(), "line 2 is 1 cells wide, expected 2");
+
+        let padded = Grid::from_lines_padded(["a", "bcd", "ef"], '.', |c| c);
+        assert_eq!((padded.width(), padded.height()), (3, 3));
+        assert_eq!(padded.column(2).collect::<String>(), ".d.");
+    }
+
+    #[test]
+    fn test_neighbors() {
+        let grid = grid();
+
+        assert_eq!(grid.neighbors4(Pos::new(0, 0)).count(), 2);
+        assert_eq!(grid.neighbors8(Pos::new(0, 0)).count(), 3);
+        assert_eq!(grid.neighbors8(Pos::new(1, 1)).count(), 8);
+
+        let around = grid.around(1, 1..3, &ADJACENT);
+        assert_eq!(
+            around.iter().map(|pos| grid[*pos]).collect::<String>(),
+            "ab1c..4"
+        );
+
+        let wrapped = grid.wrapping_neighbors(Pos::new(0, 0), &ADJACENT);
+        assert_eq!(wrapped.map(|pos| grid[pos]).collect::<String>(), "4..1b3c2");
+        assert_eq!(grid.wrapping_around(0, 0..3, &ORTHOGONAL).len(), 6);
+    }
+
+    #[test]
+    fn test_diagonals() {
+        let grid = grid();
+        let collect = |lines: Vec<Vec<(Pos, &char)>>| {
+            lines
+                .into_iter()
+                .map(|line| line.into_iter().map(|(_, c)| *c).collect::<String>())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            collect(grid.diagonals().map(Iterator::collect).collect()),
+            [".", "c.", "a24", "b3", "1"]
+        );
+        assert_eq!(
+            collect(grid.anti_diagonals().map(Iterator::collect).collect()),
+            ["a", "bc", "12.", "3.", "4"]
+        );
+    }
+
+    #[test]
+    fn test_runs() {
+        let grid = grid();
+        let runs = grid
+            .runs(|c| c.is_ascii_digit().then_some(()))
+            .map(|run| (run.row, run.cols))
+            .collect::<Vec<_>>();
+
+        assert_eq!(runs, [(0, 2..3), (1, 1..3), (2, 2..3)]);
+    }
+}
@@ -1,11 +1,59 @@
 use std::io::BufRead;
 
-pub fn get_input<S: FromIterator<String>>() -> S {
-    std::io::stdin()
-        .lock()
-        .lines()
-        .map_while(Result::ok)
-        .collect()
+pub mod args;
+pub mod automaton;
+pub mod batch;
+pub mod cancel;
+pub mod checked;
+pub mod grid;
+pub mod json;
+pub mod rng;
+pub mod runner;
+pub mod table;
+pub mod trace;
+
+pub use args::Args;
+pub use cancel::{checkpoint, Cancelled};
+pub use checked::Overflow;
+pub use grid::Grid;
+pub use runner::run;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    Overflow(Overflow),
+    Cancelled,
+    /// The input could not be parsed.
+    Parse(String),
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Overflow(err) => err.fmt(f),
+            Error::Cancelled => Cancelled.fmt(f),
+            Error::Parse(err) => f.write_str(err),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<Overflow> for Error {
+    fn from(err: Overflow) -> Self {
+        Error::Overflow(err)
+    }
+}
+
+impl From<Cancelled> for Error {
+    fn from(_: Cancelled) -> Self {
+        Error::Cancelled
+    }
+}
+
+/// Reads stdin line by line. Fails on a read error or a line that is not
+/// UTF-8, rather than stopping there as if the input had ended.
+pub fn get_input<S: FromIterator<String>>() -> std::io::Result<S> {
+    std::io::stdin().lock().lines().collect()
 }
 
 pub fn get_input_from<'a, S: FromIterator<&'a str>>(input: &'a str) -> S {
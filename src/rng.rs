@@ -0,0 +1,125 @@
+//! Small seedable pseudo-random number generator for simulations and tests.
+//!
+//! This is xoshiro256**, seeded through SplitMix64. It is fast and has good
+//! statistical quality, but it is not cryptographically secure.
+
+#[derive(Debug, Clone)]
+pub struct Rng {
+    state: [u64; 4],
+}
+
+fn splitmix64(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        let mut seed = seed;
+        Self {
+            state: std::array::from_fn(|_| splitmix64(&mut seed)),
+        }
+    }
+
+    /// Seeds from the clock, for runs that do not need to be reproducible.
+    pub fn seed_from_time() -> u64 {
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_nanos() as u64)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        let [s0, s1, s2, s3] = &mut self.state;
+
+        let result = s1.wrapping_mul(5).rotate_left(7).wrapping_mul(9);
+        let t = *s1 << 17;
+
+        *s2 ^= *s0;
+        *s3 ^= *s1;
+        *s1 ^= *s2;
+        *s0 ^= *s3;
+        *s2 ^= t;
+        *s3 = s3.rotate_left(45);
+
+        result
+    }
+
+    /// A uniform integer in `0..bound`, without modulo bias.
+    pub fn below(&mut self, bound: u64) -> u64 {
+        assert!(bound > 0, "empty range");
+
+        let zone = u64::MAX - u64::MAX % bound;
+        loop {
+            let n = self.next_u64();
+            if n < zone {
+                return n % bound;
+            }
+        }
+    }
+
+    /// A uniform integer in `range`.
+    pub fn range(&mut self, range: std::ops::RangeInclusive<u64>) -> u64 {
+        let (low, high) = range.into_inner();
+        assert!(low <= high, "empty range");
+
+        match (high - low).checked_add(1) {
+            Some(len) => low + self.below(len),
+            None => self.next_u64(),
+        }
+    }
+
+    /// A uniform float in `[0, 1)`.
+    pub fn unit(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// Moves a uniform sample of `len` items to the front of `items`, in
+    /// random order, and returns it.
+    pub fn sample<'a, T>(&mut self, items: &'a mut [T], len: usize) -> &'a mut [T] {
+        let len = len.min(items.len());
+        for idx in 0..len {
+            let other = idx + self.below((items.len() - idx) as u64) as usize;
+            items.swap(idx, other);
+        }
+
+        &mut items[..len]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_seeded() {
+        let mut a = Rng::new(42);
+        let mut b = Rng::new(42);
+        let mut c = Rng::new(43);
+
+        let a = (0..8).map(|_| a.next_u64()).collect::<Vec<_>>();
+        assert_eq!(a, (0..8).map(|_| b.next_u64()).collect::<Vec<_>>());
+        assert_ne!(a, (0..8).map(|_| c.next_u64()).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_ranges() {
+        let mut rng = Rng::new(7);
+
+        let mut seen = [0; 6];
+        for _ in 0..6000 {
+            seen[rng.range(3..=8) as usize - 3] += 1;
+        }
+        assert!(seen.iter().all(|count| (800..1200).contains(count)));
+
+        assert!((0..1000).all(|_| (0.0..1.0).contains(&rng.unit())));
+
+        let mut items = [1, 2, 3, 4, 5];
+        let mut sample = rng.sample(&mut items, 3).to_vec();
+        sample.sort();
+        sample.dedup();
+        assert_eq!(sample.len(), 3);
+    }
+}
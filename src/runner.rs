@@ -0,0 +1,567 @@
+//! Runs a day's solvers on a worker thread under a time budget.
+
+use std::process::ExitCode;
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+
+use crate::batch;
+use crate::cancel::{self, Token};
+use crate::trace::{self, Level, Sink};
+use crate::{Args, Error};
+
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Exit code used when a run exceeds its time budget, as with `timeout(1)`.
+pub const TIMEOUT_EXIT_CODE: u8 = 124;
+
+/// Exit code used for invalid command line arguments.
+pub const USAGE_EXIT_CODE: u8 = 2;
+
+#[derive(Debug, Clone)]
+pub struct Options {
+    pub timeout: Duration,
+    pub log_level: Level,
+    pub log_file: Option<std::path::PathBuf>,
+    pub inputs_dir: Option<std::path::PathBuf>,
+    pub expected: Option<std::path::PathBuf>,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            timeout: DEFAULT_TIMEOUT,
+            log_level: trace::DEFAULT_LEVEL,
+            log_file: None,
+            inputs_dir: None,
+            expected: None,
+        }
+    }
+}
+
+impl Options {
+    /// Reads `--timeout SECONDS`, `--log LEVEL` and `--log-file PATH`, falling
+    /// back to the `AOC_TIMEOUT`, `AOC_LOG` and `AOC_LOG_FILE` variables, and
+    /// the batch options `--inputs-dir DIR` and `--expected FILE`.
+    pub fn parse(args: &mut Args) -> Result<Self, String> {
+        let mut options = Self::default();
+
+        if let Some(timeout) = args
+            .value("timeout")?
+            .or_else(|| std::env::var("AOC_TIMEOUT").ok())
+        {
+            options.timeout = timeout
+                .parse::<f64>()
+                .ok()
+                .and_then(|secs| Duration::try_from_secs_f64(secs).ok())
+                .ok_or_else(|| format!("invalid timeout `{timeout}`"))?;
+        }
+
+        if let Some(level) = args.value("log")?.or_else(|| std::env::var("AOC_LOG").ok()) {
+            options.log_level = level.parse()?;
+        }
+
+        options.log_file = args
+            .value("log-file")?
+            .or_else(|| std::env::var("AOC_LOG_FILE").ok())
+            .map(Into::into);
+
+        options.inputs_dir = args.value("inputs-dir")?.map(Into::into);
+        options.expected = args.value("expected")?.map(Into::into);
+        if options.expected.is_some() && options.inputs_dir.is_none() {
+            return Err("--expected requires --inputs-dir".to_string());
+        }
+
+        Ok(options)
+    }
+
+    /// Installs the global logger described by these options.
+    pub fn init_logging(&self) -> Result<(), String> {
+        let sink = match &self.log_file {
+            Some(path) => {
+                let file = std::fs::File::create(path)
+                    .map_err(|err| format!("cannot create {}: {err}", path.display()))?;
+                Sink::Json(Box::new(std::io::LineWriter::new(file)))
+            }
+            None => Sink::Stderr,
+        };
+
+        trace::init(self.log_level, sink).map_err(|err| err.to_string())?;
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Outcome {
+    Solved(String),
+    Failed(String),
+    /// The input could not be parsed, so the part never ran.
+    Invalid(String),
+    TimedOut(Duration),
+}
+
+impl std::fmt::Display for Outcome {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Outcome::Solved(answer) => f.write_str(answer),
+            Outcome::Failed(err) => write!(f, "error: {err}"),
+            Outcome::Invalid(err) => write!(f, "invalid input: {err}"),
+            Outcome::TimedOut(timeout) => write!(f, "timed out after {timeout:?}"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Report {
+    pub part_1: Outcome,
+    pub part_2: Outcome,
+    pub elapsed: Duration,
+}
+
+impl Report {
+    /// Why the input could not be parsed, if it could not.
+    pub fn parse_error(&self) -> Option<&str> {
+        match &self.part_1 {
+            Outcome::Invalid(err) => Some(err),
+            _ => None,
+        }
+    }
+
+    pub fn exit_code(&self) -> ExitCode {
+        let outcomes = [&self.part_1, &self.part_2];
+
+        if outcomes
+            .iter()
+            .any(|outcome| matches!(outcome, Outcome::TimedOut(_)))
+        {
+            ExitCode::from(TIMEOUT_EXIT_CODE)
+        } else if outcomes
+            .iter()
+            .any(|outcome| matches!(outcome, Outcome::Failed(_) | Outcome::Invalid(_)))
+        {
+            ExitCode::FAILURE
+        } else {
+            ExitCode::SUCCESS
+        }
+    }
+}
+
+/// Parses `lines` with `parse` and runs both parts, giving up once
+/// `options.timeout` has elapsed. A timed out run is cancelled so its
+/// checkpoints start failing. If parsing fails, both parts report the
+/// parse error as [`Outcome::Invalid`].
+pub fn solve<S, A, B>(
+    day: u8,
+    lines: Vec<String>,
+    options: &Options,
+    parse: impl FnOnce(Vec<String>) -> Result<S, Error> + Send + 'static,
+    part_1: impl FnOnce(&S) -> Result<A, Error> + Send + 'static,
+    part_2: impl FnOnce(&S) -> Result<B, Error> + Send + 'static,
+) -> Report
+where
+    A: std::fmt::Display,
+    B: std::fmt::Display,
+{
+    let token = Token::default();
+    let (parsed_tx, parsed_rx) = mpsc::channel();
+    let (tx, rx) = mpsc::channel();
+
+    std::thread::spawn({
+        let token = token.clone();
+        move || {
+            cancel::install(token);
+
+            let _day = trace::span(format!("day {day}"));
+
+            let state = {
+                let _parse = trace::span("parse");
+                parse(lines)
+            };
+            let state = match state {
+                Ok(state) => state,
+                Err(err) => {
+                    parsed_tx.send(Err(err)).ok();
+                    return;
+                }
+            };
+            parsed_tx.send(Ok(())).ok();
+
+            let answer = {
+                let _part = trace::span("part 1");
+                part_1(&state).map(|answer| answer.to_string())
+            };
+            if tx.send(answer).is_err() {
+                return;
+            }
+
+            let answer = {
+                let _part = trace::span("part 2");
+                part_2(&state).map(|answer| answer.to_string())
+            };
+            tx.send(answer).ok();
+        }
+    });
+
+    let start = Instant::now();
+    let deadline = start + options.timeout;
+
+    let parsed = match parsed_rx.recv_timeout(deadline.saturating_duration_since(start)) {
+        Ok(Ok(())) => None,
+        Ok(Err(Error::Cancelled)) | Err(mpsc::RecvTimeoutError::Timeout) => {
+            token.cancel();
+            Some(Outcome::TimedOut(options.timeout))
+        }
+        Ok(Err(err)) => Some(Outcome::Invalid(err.to_string())),
+        Err(mpsc::RecvTimeoutError::Disconnected) => Some(Outcome::Failed("panicked".to_string())),
+    };
+    if let Some(outcome) = parsed {
+        return Report {
+            part_1: outcome.clone(),
+            part_2: outcome,
+            elapsed: start.elapsed(),
+        };
+    }
+
+    let next = || {
+        if token.is_cancelled() {
+            return Outcome::TimedOut(options.timeout);
+        }
+
+        match rx.recv_timeout(deadline.saturating_duration_since(Instant::now())) {
+            Ok(Ok(answer)) => Outcome::Solved(answer),
+            Ok(Err(Error::Cancelled)) | Err(mpsc::RecvTimeoutError::Timeout) => {
+                token.cancel();
+                Outcome::TimedOut(options.timeout)
+            }
+            Ok(Err(err)) => Outcome::Failed(err.to_string()),
+            Err(mpsc::RecvTimeoutError::Disconnected) => Outcome::Failed("panicked".to_string()),
+        }
+    };
+
+    let part_1 = next();
+    let part_2 = next();
+
+    Report {
+        part_1,
+        part_2,
+        elapsed: start.elapsed(),
+    }
+}
+
+/// Runs `task` on a worker thread under `options.timeout`, like [`solve`]
+/// runs the parts. Returns `None` if it timed out, cancelling the task so its
+/// checkpoints start failing. A panic in `task` resumes on this thread.
+pub fn within<T: Send + 'static>(
+    options: &Options,
+    task: impl FnOnce() -> T + Send + 'static,
+) -> Option<T> {
+    let token = Token::default();
+    let (tx, rx) = mpsc::channel();
+
+    let worker = std::thread::spawn({
+        let token = token.clone();
+        move || {
+            cancel::install(token);
+            tx.send(task()).ok();
+        }
+    });
+
+    match rx.recv_timeout(options.timeout) {
+        Ok(value) => Some(value),
+        Err(mpsc::RecvTimeoutError::Timeout) => {
+            token.cancel();
+            None
+        }
+        Err(mpsc::RecvTimeoutError::Disconnected) => {
+            std::panic::resume_unwind(worker.join().unwrap_err())
+        }
+    }
+}
+
+/// Reports an invalid command line and returns [`USAGE_EXIT_CODE`].
+pub fn usage_error(day: u8, err: impl std::fmt::Display) -> ExitCode {
+    eprintln!("day {day}: {err}");
+    ExitCode::from(USAGE_EXIT_CODE)
+}
+
+/// Entry point of the modes that analyze the input instead of solving the
+/// parts: parses stdin with `parse` and runs `task` on the result, like
+/// [`run_task`] runs a task.
+pub fn run_mode<S, T, E>(
+    day: u8,
+    args: Args,
+    parse: impl FnOnce(Vec<String>) -> Result<S, Error> + Send + 'static,
+    task: impl FnOnce(&S) -> Result<T, E> + Send + 'static,
+) -> ExitCode
+where
+    T: std::fmt::Display + Send + 'static,
+    E: std::fmt::Display,
+{
+    run_task(day, args, move || {
+        let lines = crate::get_input().map_err(|err| format!("cannot read stdin: {err}"))?;
+        let state = {
+            let _parse = trace::span("parse");
+            parse(lines).map_err(|err| err.to_string())?
+        };
+        task(&state).map_err(|err| err.to_string())
+    })
+}
+
+/// Entry point of the modes that read stdin themselves: reads the runner
+/// options left in `args` and runs `task` under the time budget, printing
+/// what it returns. A failed task is reported on stderr like [`run_parsed`]
+/// reports a parse error.
+pub fn run_task<T, E>(
+    day: u8,
+    mut args: Args,
+    task: impl FnOnce() -> Result<T, E> + Send + 'static,
+) -> ExitCode
+where
+    T: std::fmt::Display + Send + 'static,
+    E: std::fmt::Display,
+{
+    let options = match Options::parse(&mut args).and_then(|options| {
+        args.finish()?;
+        if options.inputs_dir.is_some() {
+            return Err("--inputs-dir only applies when solving".to_string());
+        }
+        options.init_logging()?;
+        Ok(options)
+    }) {
+        Ok(options) => options,
+        Err(err) => return usage_error(day, err),
+    };
+
+    let result = within(&options, move || {
+        let _day = trace::span(format!("day {day}"));
+        task().map_err(|err| err.to_string())
+    });
+
+    match result {
+        Some(Ok(output)) => {
+            let output = output.to_string();
+            if !output.is_empty() {
+                println!("{output}");
+            }
+            ExitCode::SUCCESS
+        }
+        Some(Err(err)) => {
+            for line in err.lines() {
+                eprintln!("day {day}: {line}");
+            }
+            ExitCode::FAILURE
+        }
+        None => {
+            eprintln!("day {day}: {}", Outcome::TimedOut(options.timeout));
+            ExitCode::from(TIMEOUT_EXIT_CODE)
+        }
+    }
+}
+
+/// Entry point of the day binaries: reads stdin, solves both parts and
+/// prints the answers. With `--inputs-dir` every file in the directory is
+/// solved instead and the results are printed as a table.
+pub fn run<S, A, B>(
+    day: u8,
+    part_1: impl Fn(&S) -> Result<A, Error> + Clone + Send + 'static,
+    part_2: impl Fn(&S) -> Result<B, Error> + Clone + Send + 'static,
+) -> ExitCode
+where
+    S: FromIterator<String>,
+    A: std::fmt::Display,
+    B: std::fmt::Display,
+{
+    run_with(day, Args::from_env(), part_1, part_2)
+}
+
+/// Like [`run`], for days that consume their own flags from `args` first.
+pub fn run_with<S, A, B>(
+    day: u8,
+    args: Args,
+    part_1: impl Fn(&S) -> Result<A, Error> + Clone + Send + 'static,
+    part_2: impl Fn(&S) -> Result<B, Error> + Clone + Send + 'static,
+) -> ExitCode
+where
+    S: FromIterator<String>,
+    A: std::fmt::Display,
+    B: std::fmt::Display,
+{
+    run_parsed(
+        day,
+        args,
+        |lines: Vec<String>| Ok(lines.into_iter().collect::<S>()),
+        part_1,
+        part_2,
+    )
+}
+
+/// Like [`run_with`], for days whose input can be invalid. A parse error is
+/// reported on stderr instead of the answers.
+pub fn run_parsed<S, A, B>(
+    day: u8,
+    mut args: Args,
+    parse: impl Fn(Vec<String>) -> Result<S, Error> + Clone + Send + 'static,
+    part_1: impl Fn(&S) -> Result<A, Error> + Clone + Send + 'static,
+    part_2: impl Fn(&S) -> Result<B, Error> + Clone + Send + 'static,
+) -> ExitCode
+where
+    A: std::fmt::Display,
+    B: std::fmt::Display,
+{
+    let options = match Options::parse(&mut args).and_then(|options| {
+        args.finish()?;
+        options.init_logging()?;
+        Ok(options)
+    }) {
+        Ok(options) => options,
+        Err(err) => return usage_error(day, err),
+    };
+
+    if let Some(dir) = &options.inputs_dir {
+        let entries = options
+            .expected
+            .as_ref()
+            .map_or(Ok(Default::default()), |path| {
+                std::fs::read_to_string(path)
+                    .map_err(|err| format!("cannot read {}: {err}", path.display()))
+                    .and_then(|contents| batch::parse_expected(&contents))
+            })
+            .and_then(|expected| {
+                batch::solve_dir(day, dir, &expected, &options, parse, part_1, part_2)
+            });
+
+        return match entries {
+            Ok(entries) => {
+                println!("{}", batch::table(&entries));
+                batch::exit_code(&entries)
+            }
+            Err(err) => {
+                eprintln!("day {day}: {err}");
+                ExitCode::FAILURE
+            }
+        };
+    }
+
+    let lines = match crate::get_input() {
+        Ok(lines) => lines,
+        Err(err) => {
+            eprintln!("day {day}: cannot read stdin: {err}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let report = solve(day, lines, &options, parse, part_1, part_2);
+    if let Some(err) = report.parse_error() {
+        for line in err.lines() {
+            eprintln!("day {day}: {line}");
+        }
+        return ExitCode::FAILURE;
+    }
+
+    println!("part 1: {}", report.part_1);
+
+    println!("part 2: {}", report.part_2);
+
+    report.exit_code()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_timeout() {
+        let options = Options {
+            timeout: Duration::from_millis(50),
+            ..Default::default()
+        };
+
+        let report = solve(
+            0,
+            Vec::new(),
+            &options,
+            Ok,
+            |_: &Vec<String>| -> Result<u32, Error> {
+                loop {
+                    cancel::checkpoint()?;
+                    std::thread::sleep(Duration::from_millis(1));
+                }
+            },
+            |_: &Vec<String>| Ok::<_, Error>(0),
+        );
+
+        assert_eq!(report.part_1, Outcome::TimedOut(options.timeout));
+        assert_eq!(report.part_2, Outcome::TimedOut(options.timeout));
+        assert_eq!(report.exit_code(), ExitCode::from(TIMEOUT_EXIT_CODE));
+    }
+
+    #[test]
+    fn test_within() {
+        let options = Options {
+            timeout: Duration::from_millis(50),
+            ..Default::default()
+        };
+
+        assert_eq!(within(&options, || 42), Some(42));
+
+        let cancelled = within(&options, || loop {
+            cancel::checkpoint()?;
+            std::thread::sleep(Duration::from_millis(1));
+        });
+        assert_eq!(cancelled, None::<Result<(), Error>>);
+    }
+
+    #[test]
+    fn test_solved() {
+        let report = solve(
+            0,
+            vec!["1".to_string(), "2".to_string()],
+            &Options::default(),
+            Ok,
+            |lines: &Vec<String>| Ok::<_, Error>(lines.len()),
+            |lines: &Vec<String>| Ok::<_, Error>(lines.concat()),
+        );
+
+        assert_eq!(report.part_1, Outcome::Solved("2".to_string()));
+        assert_eq!(report.part_2, Outcome::Solved("12".to_string()));
+    }
+
+    #[test]
+    fn test_parse_failures() {
+        let part = |_: &Vec<String>| Ok::<_, Error>(0);
+
+        let report = solve(
+            0,
+            Vec::new(),
+            &Options::default(),
+            |_| Err::<Vec<String>, _>(Error::Parse("bad line".to_string())),
+            part,
+            part,
+        );
+        assert_eq!(report.parse_error(), Some("bad line"));
+        assert_eq!(report.part_2, Outcome::Invalid("bad line".to_string()));
+        assert_eq!(report.exit_code(), ExitCode::FAILURE);
+
+        let options = Options {
+            timeout: Duration::from_millis(50),
+            ..Default::default()
+        };
+        let report = solve(
+            0,
+            Vec::new(),
+            &options,
+            |lines| loop {
+                cancel::checkpoint()?;
+                std::thread::sleep(Duration::from_millis(1));
+                if lines.len() > 1 {
+                    return Ok(lines);
+                }
+            },
+            part,
+            part,
+        );
+        assert_eq!(report.part_1, Outcome::TimedOut(options.timeout));
+        assert_eq!(report.parse_error(), None);
+        assert_eq!(report.exit_code(), ExitCode::from(TIMEOUT_EXIT_CODE));
+    }
+}
@@ -0,0 +1,45 @@
+//! Plain text tables with left-aligned columns.
+
+/// Renders `rows` with every column padded to its widest cell and two spaces
+/// between columns. Rows may have different lengths.
+pub fn render<R: AsRef<[String]>>(rows: &[R]) -> String {
+    let mut widths = Vec::<usize>::new();
+    for row in rows {
+        for (col, cell) in row.as_ref().iter().enumerate() {
+            let width = cell.chars().count();
+            match widths.get_mut(col) {
+                Some(max) => *max = (*max).max(width),
+                None => widths.push(width),
+            }
+        }
+    }
+
+    rows.iter()
+        .map(|row| {
+            row.as_ref()
+                .iter()
+                .zip(&widths)
+                .map(|(cell, width)| format!("{cell:width$}"))
+                .collect::<Vec<_>>()
+                .join("  ")
+                .trim_end()
+                .to_string()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        let rows = [
+            vec!["a".to_string(), "bb".to_string()],
+            vec!["ccc".to_string(), "d".to_string(), "e".to_string()],
+        ];
+
+        assert_eq!(render(&rows), "a    bb\nccc  d   e");
+    }
+}
@@ -0,0 +1,356 @@
+//! Lightweight leveled tracing with spans, written to stderr or to a
+//! JSON-lines file.
+//!
+//! Events are emitted with the [`error!`](crate::error), [`warn!`](crate::warn),
+//! [`info!`](crate::info), [`debug!`](crate::debug) and
+//! [`trace!`](crate::trace) macros. Each event carries the path of the spans
+//! open on the current thread, e.g. `day 5 > part 2`.
+
+use std::cell::RefCell;
+use std::io::Write;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use crate::json::escape;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    Error,
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+
+impl Level {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Level::Error => "error",
+            Level::Warn => "warn",
+            Level::Info => "info",
+            Level::Debug => "debug",
+            Level::Trace => "trace",
+        }
+    }
+}
+
+impl std::str::FromStr for Level {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "error" => Ok(Level::Error),
+            "warn" => Ok(Level::Warn),
+            "info" => Ok(Level::Info),
+            "debug" => Ok(Level::Debug),
+            "trace" => Ok(Level::Trace),
+            _ => Err(format!("invalid log level `{s}`")),
+        }
+    }
+}
+
+impl std::fmt::Display for Level {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+pub enum Sink {
+    Stderr,
+    Json(Box<dyn Write + Send>),
+}
+
+struct Logger {
+    level: Level,
+    start: Instant,
+    sink: Mutex<Sink>,
+}
+
+static LOGGER: OnceLock<Logger> = OnceLock::new();
+
+thread_local! {
+    static SPANS: RefCell<Vec<String>> = const { RefCell::new(Vec::new()) };
+}
+
+pub const DEFAULT_LEVEL: Level = Level::Warn;
+
+/// [`init`] was called after the global logger was installed, either by an
+/// earlier call or by the first event, which installs the default one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AlreadyInitialized;
+
+impl std::fmt::Display for AlreadyInitialized {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("logging is already initialized")
+    }
+}
+
+impl std::error::Error for AlreadyInitialized {}
+
+/// Installs the global logger. Fails, leaving the installed logger in place,
+/// if it was already installed.
+pub fn init(level: Level, sink: Sink) -> Result<(), AlreadyInitialized> {
+    LOGGER
+        .set(Logger::new(level, sink))
+        .map_err(|_| AlreadyInitialized)
+}
+
+fn logger() -> &'static Logger {
+    LOGGER.get_or_init(|| Logger::new(DEFAULT_LEVEL, Sink::Stderr))
+}
+
+pub fn enabled(level: Level) -> bool {
+    logger().enabled(level)
+}
+
+pub fn event(level: Level, message: std::fmt::Arguments<'_>) {
+    if enabled(level) {
+        logger().event(level, &path(), message);
+    }
+}
+
+/// The path of the spans open on the current thread, outermost first.
+fn path() -> String {
+    SPANS.with(|spans| spans.borrow().join(" > "))
+}
+
+impl Logger {
+    fn new(level: Level, sink: Sink) -> Self {
+        Self {
+            level,
+            start: Instant::now(),
+            sink: Mutex::new(sink),
+        }
+    }
+
+    fn enabled(&self, level: Level) -> bool {
+        level <= self.level
+    }
+
+    fn event(&self, level: Level, span: &str, message: std::fmt::Arguments<'_>) {
+        if !self.enabled(level) {
+            return;
+        }
+
+        let time = self.start.elapsed().as_secs_f64();
+
+        let mut sink = self.sink.lock().unwrap_or_else(|err| err.into_inner());
+        match &mut *sink {
+            Sink::Stderr => {
+                let level = level.as_str().to_ascii_uppercase();
+                if span.is_empty() {
+                    eprintln!("[{time:>9.6}s {level:>5}] {message}");
+                } else {
+                    eprintln!("[{time:>9.6}s {level:>5} {span}] {message}");
+                }
+            }
+            Sink::Json(writer) => {
+                writeln!(
+                    writer,
+                    r#"{{"time":{time},"level":"{level}","span":"{}","message":"{}"}}"#,
+                    escape(span),
+                    escape(&message.to_string()),
+                )
+                .ok();
+            }
+        }
+    }
+}
+
+/// An open span. Dropping it closes the span and logs how long it was open.
+#[must_use = "the span closes when dropped"]
+pub struct Span {
+    start: Instant,
+}
+
+impl Span {
+    pub fn elapsed(&self) -> Duration {
+        self.start.elapsed()
+    }
+}
+
+impl Drop for Span {
+    fn drop(&mut self) {
+        event(
+            Level::Info,
+            format_args!("exit after {:?}", self.start.elapsed()),
+        );
+        SPANS.with(|spans| spans.borrow_mut().pop());
+    }
+}
+
+/// Opens a span named `name` on the current thread.
+pub fn span(name: impl Into<String>) -> Span {
+    SPANS.with(|spans| spans.borrow_mut().push(name.into()));
+    event(Level::Info, format_args!("enter"));
+
+    Span {
+        start: Instant::now(),
+    }
+}
+
+#[macro_export]
+macro_rules! error {
+    ($($arg:tt)*) => {
+        $crate::trace::event($crate::trace::Level::Error, format_args!($($arg)*))
+    };
+}
+
+#[macro_export]
+macro_rules! warn {
+    ($($arg:tt)*) => {
+        $crate::trace::event($crate::trace::Level::Warn, format_args!($($arg)*))
+    };
+}
+
+#[macro_export]
+macro_rules! info {
+    ($($arg:tt)*) => {
+        $crate::trace::event($crate::trace::Level::Info, format_args!($($arg)*))
+    };
+}
+
+#[macro_export]
+macro_rules! debug {
+    ($($arg:tt)*) => {
+        $crate::trace::event($crate::trace::Level::Debug, format_args!($($arg)*))
+    };
+}
+
+#[macro_export]
+macro_rules! trace {
+    ($($arg:tt)*) => {
+        $crate::trace::event($crate::trace::Level::Trace, format_args!($($arg)*))
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::json::Value;
+
+    /// A JSON-lines sink the test can read back.
+    #[derive(Clone, Default)]
+    struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Buffer {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl Buffer {
+        fn lines(&self) -> Vec<Value> {
+            String::from_utf8(self.0.lock().unwrap().clone())
+                .unwrap()
+                .lines()
+                .map(|line| Value::parse(line).unwrap())
+                .collect()
+        }
+    }
+
+    #[test]
+    fn test_spans() {
+        assert_eq!(path(), "");
+
+        let day = span("day 5");
+        {
+            let _part = span("part 2");
+            assert_eq!(path(), "day 5 > part 2");
+        }
+        assert_eq!(path(), "day 5");
+
+        let other = std::thread::spawn(path).join().unwrap();
+        assert_eq!(other, "");
+
+        drop(day);
+        assert_eq!(path(), "");
+    }
+
+    #[test]
+    fn test_filtering() {
+        let buffer = Buffer::default();
+        let logger = Logger::new(Level::Info, Sink::Json(Box::new(buffer.clone())));
+
+        assert!(logger.enabled(Level::Error));
+        assert!(logger.enabled(Level::Info));
+        assert!(!logger.enabled(Level::Debug));
+
+        for level in [
+            Level::Trace,
+            Level::Debug,
+            Level::Info,
+            Level::Warn,
+            Level::Error,
+        ] {
+            logger.event(level, "", format_args!("{level}"));
+        }
+
+        let levels = buffer
+            .lines()
+            .iter()
+            .map(|line| {
+                line.get("level")
+                    .and_then(Value::as_str)
+                    .unwrap()
+                    .to_string()
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(levels, ["info", "warn", "error"]);
+    }
+
+    #[test]
+    fn test_json_lines() {
+        let buffer = Buffer::default();
+        let logger = Logger::new(Level::Trace, Sink::Json(Box::new(buffer.clone())));
+
+        logger.event(
+            Level::Warn,
+            "day 5 > part 2",
+            format_args!("say \"{}\"", 42),
+        );
+        logger.event(Level::Debug, "", format_args!("done"));
+
+        let lines = buffer.lines();
+        assert_eq!(lines.len(), 2);
+
+        let keys = lines[0]
+            .as_object()
+            .unwrap()
+            .iter()
+            .map(|(key, _)| key.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(keys, ["time", "level", "span", "message"]);
+        assert!(matches!(lines[0].get("time"), Some(Value::Number(time)) if *time >= 0.0));
+        assert_eq!(
+            lines[0].get("span").and_then(Value::as_str),
+            Some("day 5 > part 2")
+        );
+        assert_eq!(
+            lines[0].get("message").and_then(Value::as_str),
+            Some("say \"42\"")
+        );
+        assert_eq!(lines[1].get("level").and_then(Value::as_str), Some("debug"));
+        assert_eq!(lines[1].get("span").and_then(Value::as_str), Some(""));
+    }
+
+    #[test]
+    fn test_init_once() {
+        let _ = logger();
+        assert_eq!(init(Level::Trace, Sink::Stderr), Err(AlreadyInitialized));
+    }
+
+    #[test]
+    fn test_level_order() {
+        assert!(Level::Error < Level::Warn);
+        assert!(Level::Debug < Level::Trace);
+        assert_eq!("DEBUG".parse::<Level>(), Ok(Level::Debug));
+        assert!("loud".parse::<Level>().is_err());
+    }
+}